@@ -0,0 +1,19 @@
+use clap::Parser;
+use kniffel::cli::{sim, SimArgs};
+use std::process;
+
+/// Simulate games of one bot strategy or a tournament between strategies, the same as
+/// kniffel sim.
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(flatten)]
+    sim: SimArgs,
+}
+
+fn main() {
+    if let Err(e) = sim(Cli::parse().sim) {
+        eprintln!("{e}");
+        process::exit(1);
+    }
+}
@@ -0,0 +1,112 @@
+use clap::Args;
+
+use crate::engine::simulation::{simulate, Summary};
+use crate::engine::tournament::{run_tournament, TournamentResult};
+use crate::engine::{Difficulty, Ruleset};
+
+/// Options of `kniffel sim` and the `kniffel-sim` binary.
+#[derive(Args)]
+pub struct SimArgs {
+    #[arg(long, default_value_t = 1000)]
+    games: usize,
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    #[arg(long, value_parser = parse_rules, default_value = "kniffel")]
+    rules: Ruleset,
+    /// easy, greedy, optimal or optimal:<noise>
+    #[arg(long, value_parser = parse_strategy, default_value = "optimal")]
+    strategy: Difficulty,
+    /// play matches between these strategies instead, e.g. easy,greedy,optimal
+    #[arg(long, value_delimiter = ',')]
+    tournament: Vec<String>,
+}
+
+pub fn parse_rules(name: &str) -> Result<Ruleset, String> {
+    return Ruleset::from_name(name);
+}
+
+pub fn parse_strategy(name: &str) -> Result<Difficulty, String> {
+    return Difficulty::from_name(&format!("bot:{name}")).expect("bot names start with bot:");
+}
+
+fn print_summary(summary: &Summary) {
+    println!("games: {}", summary.n_games);
+    println!("mean: {:.2}", summary.mean);
+    println!("standard deviation: {:.2}", summary.std_dev);
+    for (percentile, score) in &summary.percentiles {
+        println!("{percentile}th percentile: {score}");
+    }
+    println!("upper bonus rate: {:.2}%", 100.0 * summary.upper_bonus_rate);
+    println!("yahtzee rate: {:.2}%", 100.0 * summary.yahtzee_rate);
+    println!("average points per box:");
+    for (score_t, average) in &summary.category_averages {
+        println!("{:<25}{:>8.2}", score_t.name(), average);
+    }
+}
+
+fn print_tournament(result: &TournamentResult) {
+    println!(
+        "{:<20}{:>8}{:>8}{:>8}{:>8}{:>8}",
+        "strategy", "elo", "wins", "losses", "ties", "mean"
+    );
+    for standing in result.leaderboard() {
+        println!(
+            "{:<20}{:>8.0}{:>8}{:>8}{:>8}{:>8.2}",
+            standing.name,
+            standing.rating,
+            standing.wins,
+            standing.losses,
+            standing.ties,
+            standing.mean_score
+        );
+    }
+    println!("wins of the row against the column:");
+    print!("{:<20}", "");
+    for standing in &result.standings {
+        print!("{:>12}", standing.name);
+    }
+    println!();
+    for (entrant, (standing, wins)) in result
+        .standings
+        .iter()
+        .zip(&result.head_to_head)
+        .enumerate()
+    {
+        print!("{:<20}", standing.name);
+        for (opponent, count) in wins.iter().enumerate() {
+            if opponent == entrant {
+                print!("{:>12}", "-");
+            } else {
+                print!("{count:>12}");
+            }
+        }
+        println!();
+    }
+}
+
+/// Simulate games of one strategy, or a tournament if strategies are given, and print the
+/// statistics.
+pub fn sim(args: SimArgs) -> Result<(), String> {
+    let rules = args.rules;
+    let (n_games, seed) = (args.games, args.seed);
+    if !args.tournament.is_empty() {
+        let entrants = args
+            .tournament
+            .into_iter()
+            .map(|name| Ok((name.clone(), parse_strategy(&name)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+        println!(
+            "playing {n_games} matches with {} rules and seed {seed}",
+            rules.name
+        );
+        print_tournament(&run_tournament(&entrants, &rules, n_games, seed)?);
+        return Ok(());
+    }
+    let difficulty = args.strategy;
+    println!(
+        "simulating {n_games} games of {difficulty:?} with {} rules and seed {seed}",
+        rules.name
+    );
+    print_summary(&simulate(difficulty, &rules, n_games, seed)?);
+    return Ok(());
+}
@@ -0,0 +1,166 @@
+use super::solver::Solver;
+use super::{Action, GameState};
+
+/// A decision of the game compared to the optimal one for the same situation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Review {
+    pub player: usize,
+    pub turn: usize,
+    pub numbers: Vec<i32>,
+    pub chosen: String,
+    pub best: String,
+    /// expected points lost against the best decision, 0 if the decision was optimal
+    pub loss: f64,
+}
+
+/// Compare every keep and score decision in the history of the game to the optimal
+/// decision for a single player.
+pub fn analyze(game: &GameState) -> Result<Vec<Review>, String> {
+    let mut solver = Solver::new(game.rules().clone())?;
+    let mut reviews = Vec::with_capacity(game.history().len());
+    for decision in game.history() {
+        let sheet = &decision.score_card.columns()[0];
+        let numbers = &decision.numbers;
+        let (chosen, value, best, best_value) = match &decision.action {
+            Action::Keep(kept) => {
+                let mut kept = kept.clone();
+                kept.sort();
+                let ranked = solver.ranked_keeps(sheet, numbers, decision.throws_left)?;
+                let value = ranked
+                    .iter()
+                    .find(|(keep, _)| *keep == kept)
+                    .map(|(_, value)| *value)
+                    .ok_or(format!("{kept:?} cannot be kept from {numbers:?}"))?;
+                let (best, best_value) = &ranked[0];
+                (
+                    format!("keep {kept:?}"),
+                    value,
+                    format!("keep {best:?}"),
+                    *best_value,
+                )
+            }
+            Action::Score(_, score_t) => {
+                let value = solver
+                    .category_value(sheet, numbers, *score_t)?
+                    .ok_or(format!("{score_t:?} cannot be written"))?;
+                let (best, best_value) = solver.best_category(sheet, numbers)?;
+                let points = |score_t| {
+                    let after = sheet.with_score(score_t, numbers, game.rules());
+                    return after.sum(game.rules()) - sheet.sum(game.rules());
+                };
+                (
+                    format!("{score_t:?} ({} points)", points(*score_t)),
+                    value,
+                    format!("{best:?} ({} points)", points(best)),
+                    best_value,
+                )
+            }
+        };
+        reviews.push(Review {
+            player: decision.player,
+            turn: decision.turn,
+            numbers: numbers.clone(),
+            chosen,
+            best,
+            loss: (best_value - value).max(0.0),
+        });
+    }
+    return Ok(reviews);
+}
+
+/// Total expected points lost per player, in seating order.
+pub fn total_losses(game: &GameState, reviews: &[Review]) -> Vec<(String, f64)> {
+    return game
+        .players()
+        .iter()
+        .enumerate()
+        .map(|(player, name)| {
+            let loss = reviews
+                .iter()
+                .filter(|review| review.player == player)
+                .map(|review| review.loss)
+                .sum();
+            return (name.clone(), loss);
+        })
+        .collect();
+}
+
+/// Print every decision with its expected loss and the totals per player.
+pub fn print_analysis(game: &GameState) {
+    println!("computing the best strategy, this takes a while");
+    let reviews = match analyze(game) {
+        Ok(reviews) => reviews,
+        Err(e) => {
+            println!("no analysis available: {e}");
+            return;
+        }
+    };
+    for review in &reviews {
+        let name = &game.players()[review.player];
+        let numbers = &review.numbers;
+        if review.loss < 1e-9 {
+            println!(
+                "turn {} {name} {numbers:?}: {} (best)",
+                review.turn, review.chosen
+            );
+            continue;
+        }
+        println!(
+            "turn {} {name} {numbers:?}: {}, best was {} (-{:.2})",
+            review.turn, review.chosen, review.best, review.loss
+        );
+    }
+    println!("expected points lost:");
+    for (name, loss) in total_losses(game, &reviews) {
+        println!("{name}: {loss:.2}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Dice, Ruleset, ScoreType};
+
+    #[test]
+    fn losses() {
+        let rules = Ruleset {
+            categories: vec![ScoreType::Sixes, ScoreType::Yahtzee, ScoreType::Chance],
+            ..Ruleset::kniffel().with_dice(3, 6)
+        };
+        let mut game = GameState::new(
+            vec![String::from("alice"), String::from("bob")],
+            Dice::scripted(vec![6, 6, 6, 6, 6, 6]),
+            rules,
+        );
+        game.roll().unwrap();
+        game.keep(vec![6, 6, 6]).unwrap();
+        game.choose_category(0, ScoreType::Yahtzee).unwrap();
+        game.advance_player().unwrap();
+        game.roll().unwrap();
+        game.keep(vec![6]).unwrap();
+
+        let reviews = analyze(&game).unwrap();
+        assert_eq!(reviews.len(), 3);
+        assert_eq!(reviews[0].chosen, "keep [6, 6, 6]");
+        assert_eq!(reviews[0].loss, 0.0);
+        assert_eq!(reviews[1].chosen, "Yahtzee (50 points)");
+        assert_eq!(reviews[1].loss, 0.0);
+        assert_eq!(reviews[2].player, 1);
+        assert_eq!(reviews[2].best, "keep [6, 6, 6]");
+        assert!(reviews[2].loss > 0.0);
+
+        let totals = total_losses(&game, &reviews);
+        assert_eq!(totals[0], (String::from("alice"), 0.0));
+        assert_eq!(totals[1].1, reviews[2].loss);
+    }
+
+    #[test]
+    fn unsupported_rules() {
+        let game = GameState::new(
+            vec![String::from("alice")],
+            Dice::seeded(1),
+            Ruleset::triple_yahtzee(),
+        );
+        assert!(analyze(&game).is_err());
+    }
+}
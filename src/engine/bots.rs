@@ -0,0 +1,277 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::{Arc, Mutex};
+
+use super::solver::Solver;
+use super::{potential_score, utils, GameState, Player, ScoreType};
+
+/// How well a computer opponent plays.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Difficulty {
+    /// random keeps and random legal boxes
+    Easy,
+    /// always takes the most points right now
+    Greedy,
+    /// maximizes the expected final score, `noise` random points are added to the value
+    /// of every choice so higher noise plays worse
+    Optimal { noise: f64 },
+}
+
+impl Difficulty {
+    /// Parse "bot:easy", "bot:greedy", "bot:optimal" or "bot:optimal:<noise>".
+    /// Returns `None` for names of human players.
+    pub fn from_name(name: &str) -> Option<Result<Difficulty, String>> {
+        let level = name.strip_prefix("bot:")?;
+        match level.split_once(':') {
+            None if level == "easy" => return Some(Ok(Difficulty::Easy)),
+            None if level == "greedy" => return Some(Ok(Difficulty::Greedy)),
+            None if level == "optimal" => return Some(Ok(Difficulty::Optimal { noise: 0.0 })),
+            Some(("optimal", noise)) => match noise.parse::<f64>() {
+                Ok(noise) if noise >= 0.0 => return Some(Ok(Difficulty::Optimal { noise })),
+                _ => return Some(Err(format!("invalid noise {noise}"))),
+            },
+            _ => return Some(Err(format!("unknown bot {name}"))),
+        }
+    }
+}
+
+/// Create a bot. Optimal bots need a solver for the rules of the game, it can be shared
+/// by all bots of a game.
+pub fn create_bot(
+    difficulty: Difficulty,
+    seed: u64,
+    solver: Option<Arc<Mutex<Solver>>>,
+) -> Result<Box<dyn Player>, String> {
+    match difficulty {
+        Difficulty::Easy => return Ok(Box::new(RandomBot::new(seed))),
+        Difficulty::Greedy => return Ok(Box::new(GreedyBot)),
+        Difficulty::Optimal { noise } => {
+            let solver = solver.ok_or(String::from("optimal bots need a solver"))?;
+            return Ok(Box::new(OptimalBot::new(solver, noise, seed)));
+        }
+    }
+}
+
+/// Keeps every die with a chance of one half and writes to a random legal box.
+pub struct RandomBot {
+    rng: StdRng,
+}
+
+impl RandomBot {
+    pub fn new(seed: u64) -> RandomBot {
+        RandomBot {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Player for RandomBot {
+    fn decide_keep_dice(&mut self, game: &GameState) -> Vec<i32> {
+        return game
+            .numbers()
+            .iter()
+            .filter(|_| self.rng.gen_bool(0.5))
+            .copied()
+            .collect();
+    }
+
+    fn decide_scoresheet_update(&mut self, game: &GameState) -> Result<(usize, ScoreType), String> {
+        let legal = game.legal_boxes();
+        if legal.is_empty() {
+            return Err(String::from("no box left to write to"));
+        }
+        return Ok(legal[self.rng.gen_range(0..legal.len())]);
+    }
+}
+
+/// Writes to the box that adds the most points right now. Keeps all dice once a shape
+/// like a straight is thrown that scores in an open box, otherwise the most frequent number.
+pub struct GreedyBot;
+
+impl GreedyBot {
+    fn points(game: &GameState, column: usize, score_t: ScoreType) -> i32 {
+        // points added to the card (with bonuses and column multiplier) by writing the box
+        let rules = game.rules();
+        let sheet = &game.current_score_card().columns()[column];
+        let after = sheet.with_score(score_t, game.numbers(), rules);
+        return rules.columns[column].multiplier * (after.sum(rules) - sheet.sum(rules));
+    }
+}
+
+impl Player for GreedyBot {
+    fn decide_keep_dice(&mut self, game: &GameState) -> Vec<i32> {
+        let numbers = game.numbers();
+        let shapes = [
+            ScoreType::FullHouse,
+            ScoreType::SmallStraight,
+            ScoreType::LargeStraight,
+            ScoreType::FullStraight,
+            ScoreType::Yahtzee,
+        ];
+        let shape_scores = game.legal_boxes().into_iter().any(|(column, score_t)| {
+            let sheet = &game.current_score_card().columns()[column];
+            return shapes.contains(&score_t)
+                && potential_score(sheet, score_t, numbers, game.rules()) > 0;
+        });
+        if shape_scores {
+            return numbers.to_vec();
+        }
+        let most_frequent = utils::count_unique_elements(numbers)
+            .into_iter()
+            .max_by_key(|&(num, count)| (count, num))
+            .map(|(num, _)| num);
+        return numbers
+            .iter()
+            .filter(|num| Some(**num) == most_frequent)
+            .copied()
+            .collect();
+    }
+
+    fn decide_scoresheet_update(&mut self, game: &GameState) -> Result<(usize, ScoreType), String> {
+        return game
+            .legal_boxes()
+            .into_iter()
+            .max_by_key(|(column, score_t)| GreedyBot::points(game, *column, *score_t))
+            .ok_or(String::from("no box left to write to"));
+    }
+}
+
+/// Follows the strategy of the solver, which plays a single free column.
+pub struct OptimalBot {
+    solver: Arc<Mutex<Solver>>,
+    noise: f64,
+    rng: StdRng,
+}
+
+impl OptimalBot {
+    pub fn new(solver: Arc<Mutex<Solver>>, noise: f64, seed: u64) -> OptimalBot {
+        OptimalBot {
+            solver,
+            noise,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    fn pick<T>(&mut self, ranked: Vec<(T, f64)>) -> Option<T> {
+        // the choice with the highest value after adding noise
+        if self.noise == 0.0 {
+            return ranked.into_iter().next().map(|(choice, _)| choice);
+        }
+        let noisy = ranked
+            .into_iter()
+            .map(|(choice, value)| (choice, value + self.rng.gen_range(0.0..self.noise)));
+        return noisy
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(choice, _)| choice);
+    }
+}
+
+impl Player for OptimalBot {
+    fn decide_keep_dice(&mut self, game: &GameState) -> Vec<i32> {
+        let sheet = &game.current_score_card().columns()[0];
+        let ranked =
+            self.solver
+                .lock()
+                .unwrap()
+                .ranked_keeps(sheet, game.numbers(), game.throws_left());
+        match ranked {
+            Ok(ranked) => return self.pick(ranked).unwrap_or_default(),
+            Err(_) => return game.numbers().to_vec(),
+        }
+    }
+
+    fn decide_scoresheet_update(&mut self, game: &GameState) -> Result<(usize, ScoreType), String> {
+        let sheet = &game.current_score_card().columns()[0];
+        let ranked = self
+            .solver
+            .lock()
+            .unwrap()
+            .ranked_categories(sheet, game.numbers())?;
+        return self
+            .pick(ranked)
+            .map(|score_t| (0, score_t))
+            .ok_or(String::from("no box left to write to"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{play_game_with_players, Dice, Ruleset};
+
+    fn small_rules() -> Ruleset {
+        return Ruleset {
+            categories: vec![
+                ScoreType::Fives,
+                ScoreType::Sixes,
+                ScoreType::FullHouse,
+                ScoreType::Yahtzee,
+                ScoreType::Chance,
+            ],
+            ..Ruleset::kniffel().with_dice(3, 6)
+        };
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(Difficulty::from_name("alice"), None);
+        assert_eq!(
+            Difficulty::from_name("bot:easy"),
+            Some(Ok(Difficulty::Easy))
+        );
+        assert_eq!(
+            Difficulty::from_name("bot:optimal:2.5"),
+            Some(Ok(Difficulty::Optimal { noise: 2.5 }))
+        );
+        assert!(Difficulty::from_name("bot:optimal:-1").unwrap().is_err());
+        assert!(Difficulty::from_name("bot:hard").unwrap().is_err());
+    }
+
+    #[test]
+    fn greedy_choices() {
+        let mut game = GameState::new(
+            vec![String::from("bot")],
+            Dice::scripted(vec![2, 5, 5]),
+            small_rules(),
+        );
+        game.roll().unwrap();
+        assert_eq!(GreedyBot.decide_keep_dice(&game), vec![5, 5]);
+        assert_eq!(
+            GreedyBot.decide_scoresheet_update(&game),
+            Ok((0, ScoreType::Chance))
+        );
+    }
+
+    #[test]
+    fn bots_play_full_games() {
+        let rules = small_rules();
+        let solver = Arc::new(Mutex::new(Solver::new(rules.clone()).unwrap()));
+        let difficulties = [
+            Difficulty::Easy,
+            Difficulty::Greedy,
+            Difficulty::Optimal { noise: 0.0 },
+            Difficulty::Optimal { noise: 3.0 },
+        ];
+        let seats: Vec<(String, Box<dyn Player>)> = difficulties
+            .iter()
+            .enumerate()
+            .map(|(i, difficulty)| {
+                let bot = create_bot(*difficulty, i as u64, Some(solver.clone())).unwrap();
+                return (format!("bot{i}"), bot);
+            })
+            .collect();
+        let results = play_game_with_players(seats, Dice::seeded(11), rules);
+        assert_eq!(results.len(), 4);
+        assert!(create_bot(Difficulty::Optimal { noise: 0.0 }, 0, None).is_err());
+    }
+
+    #[test]
+    fn easy_and_greedy_play_columns() {
+        let seats: Vec<(String, Box<dyn Player>)> = vec![
+            (String::from("easy"), Box::new(RandomBot::new(1))),
+            (String::from("greedy"), Box::new(GreedyBot)),
+        ];
+        let results = play_game_with_players(seats, Dice::seeded(3), Ruleset::kniffel_block());
+        assert_eq!(results.len(), 2);
+    }
+}
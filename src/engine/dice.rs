@@ -0,0 +1,301 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{HashMap, VecDeque};
+
+/// Where the values of single dice come from.
+pub trait DiceSource {
+    /// Return the value of one die between 1 and `n_faces`.
+    fn roll_die(&mut self, n_faces: i32) -> i32;
+
+    /// The seed and the number of dice rolled so far if the source can be restored from
+    /// them, e.g. to save a game.
+    fn position(&self) -> Option<(u64, u64)> {
+        return None;
+    }
+}
+
+/// Pseudo random dice that produce the same sequence for the same seed.
+pub struct SeededDiceSource {
+    rng: StdRng,
+    seed: u64,
+    n_rolls: u64,
+}
+
+impl SeededDiceSource {
+    pub fn new(seed: u64) -> SeededDiceSource {
+        SeededDiceSource {
+            rng: StdRng::seed_from_u64(seed),
+            seed,
+            n_rolls: 0,
+        }
+    }
+
+    /// Continue the sequence of `seed` after `n_rolls` dice with `n_faces` faces.
+    pub fn at_position(seed: u64, n_rolls: u64, n_faces: i32) -> SeededDiceSource {
+        let mut source = SeededDiceSource::new(seed);
+        for _ in 0..n_rolls {
+            source.roll_die(n_faces);
+        }
+        return source;
+    }
+}
+
+impl DiceSource for SeededDiceSource {
+    fn roll_die(&mut self, n_faces: i32) -> i32 {
+        self.n_rolls += 1;
+        return self.rng.gen_range(1..=n_faces);
+    }
+
+    fn position(&self) -> Option<(u64, u64)> {
+        return Some((self.seed, self.n_rolls));
+    }
+}
+
+/// Replays a fixed list of die values, e.g. for tests or to reproduce a bug report.
+pub struct ScriptedDiceSource {
+    rolls: VecDeque<i32>,
+}
+
+impl ScriptedDiceSource {
+    pub fn new(rolls: Vec<i32>) -> ScriptedDiceSource {
+        ScriptedDiceSource {
+            rolls: rolls.into(),
+        }
+    }
+}
+
+impl DiceSource for ScriptedDiceSource {
+    fn roll_die(&mut self, n_faces: i32) -> i32 {
+        let roll = self
+            .rolls
+            .pop_front()
+            .expect("scripted dice source ran out of rolls");
+        assert!(
+            roll >= 1 && roll <= n_faces,
+            "scripted roll {roll} is not on a die with {n_faces} faces"
+        );
+        return roll;
+    }
+}
+
+pub struct Dice {
+    source: Box<dyn DiceSource>,
+}
+
+impl Dice {
+    pub fn new(source: Box<dyn DiceSource>) -> Dice {
+        Dice { source }
+    }
+
+    pub fn seeded(seed: u64) -> Dice {
+        return Dice::new(Box::new(SeededDiceSource::new(seed)));
+    }
+
+    pub fn scripted(rolls: Vec<i32>) -> Dice {
+        return Dice::new(Box::new(ScriptedDiceSource::new(rolls)));
+    }
+
+    /// See `DiceSource::position`.
+    pub fn position(&self) -> Option<(u64, u64)> {
+        return self.source.position();
+    }
+
+    /// Draw a seed for a new game from the operating system's randomness.
+    pub fn random_seed() -> u64 {
+        return rand::thread_rng().gen();
+    }
+
+    /// Throw `n` dice, the numbers are in the order the dice were thrown.
+    pub fn gen_numbers(&mut self, n: usize, n_faces: i32) -> Vec<i32> {
+        let mut res = Vec::with_capacity(n);
+        for _i in 0..n {
+            res.push(self.source.roll_die(n_faces));
+        }
+        return res;
+    }
+}
+
+/// Every combination of kept or thrown dice for exact calculations over the throws of a
+/// turn. Combinations are sorted and indexed, ordered by their number of dice.
+#[derive(Clone)]
+pub(crate) struct DiceSets {
+    n_dice: usize,
+    n_faces: i32,
+    sets: Vec<Vec<i32>>,
+    index: HashMap<Vec<i32>, usize>,
+    /// index of the set with one more die showing face f + 1, empty for full throws
+    add_die: Vec<Vec<usize>>,
+    /// for full throws: the sets that can be kept from it, empty otherwise
+    keeps: Vec<Vec<usize>>,
+}
+
+impl DiceSets {
+    pub(crate) fn new(n_dice: usize, n_faces: i32) -> DiceSets {
+        let mut sets = vec![vec![]];
+        let mut last_size = vec![vec![]];
+        for _ in 0..n_dice {
+            let mut next_size = Vec::new();
+            for dice in &last_size {
+                let lowest = *dice.last().unwrap_or(&1);
+                for face in lowest..=n_faces {
+                    let mut bigger: Vec<i32> = dice.clone();
+                    bigger.push(face);
+                    next_size.push(bigger);
+                }
+            }
+            sets.extend(next_size.iter().cloned());
+            last_size = next_size;
+        }
+        let index: HashMap<Vec<i32>, usize> = sets
+            .iter()
+            .enumerate()
+            .map(|(i, dice)| (dice.clone(), i))
+            .collect();
+
+        let mut add_die = Vec::with_capacity(sets.len());
+        let mut keeps = Vec::with_capacity(sets.len());
+        for dice in &sets {
+            if dice.len() < n_dice {
+                let with_die = (1..=n_faces).map(|face| {
+                    let mut bigger = dice.clone();
+                    bigger.push(face);
+                    bigger.sort();
+                    return index[&bigger];
+                });
+                add_die.push(with_die.collect());
+                keeps.push(vec![]);
+                continue;
+            }
+            let mut subsets: Vec<usize> = (0..1usize << dice.len())
+                .map(|mask| {
+                    let kept: Vec<i32> = (0..dice.len())
+                        .filter(|i| mask & (1 << i) != 0)
+                        .map(|i| dice[i])
+                        .collect();
+                    return index[&kept];
+                })
+                .collect();
+            subsets.sort();
+            subsets.dedup();
+            add_die.push(vec![]);
+            keeps.push(subsets);
+        }
+        DiceSets {
+            n_dice,
+            n_faces,
+            sets,
+            index,
+            add_die,
+            keeps,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        return self.sets.len();
+    }
+
+    pub(crate) fn get(&self, set: usize) -> &[i32] {
+        return &self.sets[set];
+    }
+
+    /// Index of the set with the given numbers in any order.
+    pub(crate) fn find(&self, numbers: &[i32]) -> Option<usize> {
+        let mut sorted = numbers.to_vec();
+        sorted.sort();
+        return self.index.get(&sorted).copied();
+    }
+
+    pub(crate) fn full_throws(&self) -> impl Iterator<Item = usize> + '_ {
+        return (0..self.sets.len()).filter(|set| self.sets[*set].len() == self.n_dice);
+    }
+
+    /// The sets that can be kept from a full throw.
+    pub(crate) fn keeps(&self, throw: usize) -> &[usize] {
+        return &self.keeps[throw];
+    }
+
+    /// Expected value of keeping every set and throwing the other dice, given the values
+    /// of the full throws. Computed by adding one die after the other.
+    pub(crate) fn keep_values(&self, throw_values: &[f64]) -> Vec<f64> {
+        let mut values = throw_values.to_vec();
+        for set in (0..self.sets.len()).rev() {
+            if self.sets[set].len() == self.n_dice {
+                continue;
+            }
+            let sum: f64 = self.add_die[set].iter().map(|bigger| values[*bigger]).sum();
+            values[set] = sum / self.n_faces as f64;
+        }
+        return values;
+    }
+
+    /// Values of the full throws with one more throw left, keeping the best set each time.
+    pub(crate) fn rethrow_values(&self, throw_values: &[f64]) -> Vec<f64> {
+        let keep_values = self.keep_values(throw_values);
+        let mut values = throw_values.to_vec();
+        for (throw, keeps) in self.keeps.iter().enumerate() {
+            if let Some(best) = keeps.iter().map(|keep| keep_values[*keep]).reduce(f64::max) {
+                values[throw] = best;
+            }
+        }
+        return values;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let mut dice1 = Dice::seeded(42);
+        let mut dice2 = Dice::seeded(42);
+        for _ in 0..20 {
+            let numbers = dice1.gen_numbers(5, 6);
+            assert!(numbers.iter().all(|&x| (1..=6).contains(&x)));
+            assert_eq!(numbers, dice2.gen_numbers(5, 6));
+        }
+    }
+
+    #[test]
+    fn continue_at_position() {
+        let mut dice = Dice::seeded(42);
+        dice.gen_numbers(5, 6);
+        dice.gen_numbers(2, 6);
+        assert_eq!(dice.position(), Some((42, 7)));
+        let mut resumed = Dice::new(Box::new(SeededDiceSource::at_position(42, 7, 6)));
+        assert_eq!(resumed.gen_numbers(5, 6), dice.gen_numbers(5, 6));
+        assert_eq!(Dice::scripted(vec![1]).position(), None);
+    }
+
+    #[test]
+    fn scripted_numbers() {
+        let mut dice = Dice::scripted(vec![6, 1, 3, 2, 2, 5]);
+        assert_eq!(dice.gen_numbers(5, 6), vec![6, 1, 3, 2, 2]);
+        assert_eq!(dice.gen_numbers(1, 6), vec![5]);
+    }
+
+    #[test]
+    fn dice_sets() {
+        let sets = DiceSets::new(5, 6);
+        assert_eq!(sets.len(), 462);
+        assert_eq!(sets.full_throws().count(), 252);
+        let throw = sets.find(&[6, 1, 6, 1, 1]).unwrap();
+        assert_eq!(sets.get(throw), [1, 1, 1, 6, 6]);
+        // nothing, 1 to 3 ones, each with 0 to 2 sixes
+        assert_eq!(sets.keeps(throw).len(), 12);
+
+        let values: Vec<f64> = (0..sets.len())
+            .map(|set| sets.get(set).iter().sum::<i32>() as f64)
+            .collect();
+        let keep_values = sets.keep_values(&values);
+        assert!((keep_values[0] - 17.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn more_faces() {
+        let mut dice = Dice::seeded(5);
+        let numbers = dice.gen_numbers(200, 8);
+        assert!(numbers.iter().all(|&x| (1..=8).contains(&x)));
+        assert!(numbers.contains(&8));
+    }
+}
@@ -0,0 +1,1064 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use super::{
+    check_score_type_among, update_score_sheet, validate_kept_numbers, ColumnOrder, Dice, Ruleset,
+    ScoreCard, ScoreType, SeededDiceSource,
+};
+
+/// Version of the save file format, increased on incompatible changes.
+const SAVE_VERSION: u32 = 1;
+
+/// A choice a player made during the game.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Action {
+    /// the numbers kept for the next throw
+    Keep(Vec<i32>),
+    /// the column and box the numbers were written to
+    Score(usize, ScoreType),
+}
+
+/// A decision together with the situation it was made in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Decision {
+    pub player: usize,
+    pub turn: usize,
+    pub numbers: Vec<i32>,
+    /// the dice held during the last throw, by position. Empty in histories saved before
+    /// dice were held by position, there the kept numbers come first
+    #[serde(default)]
+    pub held: Vec<bool>,
+    /// throws left in the turn when the decision was made
+    pub throws_left: usize,
+    /// the score card of the player before the decision
+    pub score_card: ScoreCard,
+    pub action: Action,
+}
+
+/// Everything needed to continue a game exactly where it stopped.
+#[derive(Serialize, Deserialize)]
+struct SavedGame {
+    version: u32,
+    players: Vec<String>,
+    score_cards: Vec<ScoreCard>,
+    rules: Ruleset,
+    turn: usize,
+    current_player: usize,
+    numbers: Vec<i32>,
+    #[serde(default)]
+    held: Vec<bool>,
+    n_throws: usize,
+    saved_throws: Vec<usize>,
+    announcement: Option<(usize, ScoreType)>,
+    scored: bool,
+    history: Vec<Decision>,
+    /// seed and number of dice rolled, `None` if the dice cannot be restored
+    dice: Option<(u64, u64)>,
+}
+
+fn held_numbers(numbers: &[i32], held: &[bool]) -> Vec<i32> {
+    return numbers
+        .iter()
+        .zip(held)
+        .filter(|(_, held)| **held)
+        .map(|(num, _)| *num)
+        .collect();
+}
+
+/// Headless state of a running game. Every step of a turn is an explicit call,
+/// no input or output happens in here.
+pub struct GameState {
+    players: Vec<String>,
+    score_cards: Vec<ScoreCard>,
+    dice: Dice,
+    rules: Ruleset,
+    turn: usize,
+    current_player: usize,
+    numbers: Vec<i32>,
+    /// which dice are held and not thrown again, by position
+    held: Vec<bool>,
+    n_throws: usize,
+    saved_throws: Vec<usize>,
+    /// column and box announced after the first throw of this turn
+    announcement: Option<(usize, ScoreType)>,
+    scored: bool,
+    history: Vec<Decision>,
+}
+
+impl GameState {
+    pub fn new(players: Vec<String>, dice: Dice, rules: Ruleset) -> GameState {
+        let score_cards = players.iter().map(|_| ScoreCard::new(&rules)).collect();
+        let saved_throws = vec![0; players.len()];
+        GameState {
+            players,
+            score_cards,
+            dice,
+            turn: 1,
+            current_player: 0,
+            numbers: Vec::with_capacity(rules.n_dice),
+            held: vec![false; rules.n_dice],
+            n_throws: 0,
+            saved_throws,
+            announcement: None,
+            scored: false,
+            history: Vec::new(),
+            rules,
+        }
+    }
+
+    pub fn rules(&self) -> &Ruleset {
+        return &self.rules;
+    }
+
+    pub fn players(&self) -> &[String] {
+        return &self.players;
+    }
+
+    pub fn score_cards(&self) -> &[ScoreCard] {
+        return &self.score_cards;
+    }
+
+    /// Turn number, starting at 1.
+    pub fn turn(&self) -> usize {
+        return self.turn;
+    }
+
+    /// Index of the player whose turn it is.
+    pub fn current_player(&self) -> usize {
+        return self.current_player;
+    }
+
+    pub fn current_player_name(&self) -> &str {
+        return &self.players[self.current_player];
+    }
+
+    pub fn current_score_card(&self) -> &ScoreCard {
+        return &self.score_cards[self.current_player];
+    }
+
+    /// All keep and score decisions so far, in the order they were made.
+    pub fn history(&self) -> &[Decision] {
+        return &self.history;
+    }
+
+    fn record(&mut self, action: Action) {
+        self.history.push(Decision {
+            player: self.current_player,
+            turn: self.turn,
+            numbers: self.numbers.clone(),
+            held: self.held.clone(),
+            throws_left: self.throws_left(),
+            score_card: self.score_cards[self.current_player].clone(),
+            action,
+        });
+    }
+
+    /// Throws taken in this turn.
+    pub fn n_throws(&self) -> usize {
+        return self.n_throws;
+    }
+
+    /// The numbers of the held dice, in the order of the dice.
+    pub fn kept_numbers(&self) -> Vec<i32> {
+        return held_numbers(&self.numbers, &self.held);
+    }
+
+    /// Which dice are held, by position. Held dice stay held after a throw until the next
+    /// `hold` or `keep`.
+    pub fn held(&self) -> &[bool] {
+        return &self.held;
+    }
+
+    /// The dice of the last throw, every die keeps its position between the throws of a
+    /// turn. Empty before the first throw of a turn.
+    pub fn numbers(&self) -> &[i32] {
+        return &self.numbers;
+    }
+
+    /// Throws left in this turn, including the ones saved from earlier turns.
+    pub fn throws_left(&self) -> usize {
+        return self.rules.n_throws + self.saved_throws[self.current_player] - self.n_throws;
+    }
+
+    /// Throw all dice that are not held.
+    pub fn roll(&mut self) -> Result<&[i32], String> {
+        if self.is_finished() {
+            return Err(String::from("the game is finished"));
+        }
+        if self.scored {
+            return Err(String::from("a score was already written this turn"));
+        }
+        if self.throws_left() == 0 {
+            return Err(String::from("no throws left"));
+        }
+
+        let n_thrown = self.held.iter().filter(|held| !**held).count();
+        let mut thrown = self
+            .dice
+            .gen_numbers(n_thrown, self.rules.n_faces)
+            .into_iter();
+        let mut numbers = Vec::with_capacity(self.rules.n_dice);
+        for (die, held) in self.held.iter().enumerate() {
+            match held {
+                true => numbers.push(self.numbers[die]),
+                false => numbers.push(thrown.next().expect("one number per thrown die")),
+            }
+        }
+        self.numbers = numbers;
+        self.n_throws += 1;
+        return Ok(&self.numbers);
+    }
+
+    pub fn announcement(&self) -> Option<(usize, ScoreType)> {
+        return self.announcement;
+    }
+
+    /// Whether a box of an announced column may be announced now, i.e. right after the
+    /// first throw of the turn.
+    pub fn can_announce(&self) -> bool {
+        return self.n_throws == 1
+            && !self.scored
+            && self.announcement.is_none()
+            && self.open_boxes(ColumnOrder::Announced).next().is_some();
+    }
+
+    /// Whether only announced columns are left, so the turn cannot be scored without
+    /// an announcement.
+    pub fn must_announce(&self) -> bool {
+        return self.can_announce()
+            && self.open_boxes(ColumnOrder::Announced).count()
+                == self.open_boxes_in_all_columns().count();
+    }
+
+    /// Declare the box of an announced column the numbers of this turn will be written to.
+    pub fn announce(&mut self, column: usize, score_t: ScoreType) -> Result<(), String> {
+        if !self.can_announce() {
+            return Err(String::from(
+                "announcements are only possible right after the first throw",
+            ));
+        }
+        if self.rules.columns.get(column).map(|c| c.order) != Some(ColumnOrder::Announced) {
+            return Err(format!("column {} is not an announced column", column + 1));
+        }
+        let sheet = &self.current_score_card().columns()[column];
+        if !sheet.is_fillable(score_t, ColumnOrder::Announced, &self.rules) {
+            return Err(format!(
+                "{score_t:?} cannot be written in column {}",
+                column + 1
+            ));
+        }
+        self.announcement = Some((column, score_t));
+        return Ok(());
+    }
+
+    fn open_boxes(&self, order: ColumnOrder) -> impl Iterator<Item = (usize, ScoreType)> + '_ {
+        return self
+            .open_boxes_in_all_columns()
+            .filter(move |(column, _)| self.rules.columns[*column].order == order);
+    }
+
+    fn open_boxes_in_all_columns(&self) -> impl Iterator<Item = (usize, ScoreType)> + '_ {
+        let sheets = self.current_score_card().columns();
+        return sheets.iter().enumerate().flat_map(move |(column, sheet)| {
+            self.rules
+                .categories
+                .iter()
+                .filter(move |score_t| !sheet.is_already_written(**score_t))
+                .map(move |score_t| (column, *score_t))
+        });
+    }
+
+    /// Check that the current numbers may be written to the box now, respecting the order
+    /// of the column, announcements and the joker rules.
+    pub fn check_box(&self, column: usize, score_t: ScoreType) -> Result<(), String> {
+        let sheet = self
+            .current_score_card()
+            .columns()
+            .get(column)
+            .ok_or(format!("there is no column {}", column + 1))?;
+        if let Some(announced) = self.announcement {
+            if announced != (column, score_t) {
+                return Err(format!(
+                    "{:?} in column {} was announced",
+                    announced.1,
+                    announced.0 + 1
+                ));
+            }
+        }
+        let order = self.rules.columns[column].order;
+        // the joker rules choose among the boxes the order and the announcement leave open
+        let announcement = self.announcement;
+        let available = |candidate: ScoreType| match announcement {
+            Some(announced) => return announced == (column, candidate),
+            None => return sheet.is_fillable(candidate, order, &self.rules),
+        };
+        match order {
+            ColumnOrder::Free => {
+                return check_score_type_among(
+                    sheet,
+                    score_t,
+                    &self.numbers,
+                    &self.rules,
+                    available,
+                );
+            }
+            ColumnOrder::Announced if self.announcement.is_none() => {
+                return Err(format!(
+                    "column {} needs an announcement after the first throw",
+                    column + 1
+                ));
+            }
+            _ => {}
+        }
+        if sheet.is_fillable(score_t, order, &self.rules) {
+            return check_score_type_among(sheet, score_t, &self.numbers, &self.rules, available);
+        }
+        let next = self
+            .rules
+            .categories
+            .iter()
+            .find(|next| sheet.is_fillable(**next, order, &self.rules));
+        match next {
+            Some(next) => {
+                return Err(format!(
+                    "column {} has to be filled with {next:?} next",
+                    column + 1
+                ))
+            }
+            None => return Err(format!("column {} is full", column + 1)),
+        }
+    }
+
+    /// All boxes of the current player the current numbers may be written to now.
+    pub fn legal_boxes(&self) -> Vec<(usize, ScoreType)> {
+        return self
+            .open_boxes_in_all_columns()
+            .filter(|(column, score_t)| self.check_box(*column, *score_t).is_ok())
+            .collect();
+    }
+
+    fn check_keep(&self) -> Result<(), String> {
+        if self.n_throws == 0 {
+            return Err(String::from("the dice have not been thrown yet"));
+        }
+        if self.scored || self.throws_left() == 0 {
+            return Err(String::from("no throws left"));
+        }
+        return Ok(());
+    }
+
+    /// Choose the dice to hold for the next throw by position. The other dice are thrown
+    /// again by the next `roll`.
+    pub fn hold(&mut self, held: Vec<bool>) -> Result<(), String> {
+        self.check_keep()?;
+        if held.len() != self.numbers.len() {
+            return Err(format!(
+                "picked {} dice but there are {}",
+                held.len(),
+                self.numbers.len()
+            ));
+        }
+        self.record(Action::Keep(held_numbers(&self.numbers, &held)));
+        self.held = held;
+        return Ok(());
+    }
+
+    /// The dice to hold to keep the given numbers. Dice that are already held are
+    /// preferred, so they stay where they are.
+    pub fn held_for(&self, kept_numbers: &[i32]) -> Result<Vec<bool>, String> {
+        validate_kept_numbers(kept_numbers, &self.numbers, self.rules.n_faces)?;
+        let mut held = vec![false; self.numbers.len()];
+        for num in kept_numbers {
+            let free: Vec<usize> = (0..self.numbers.len())
+                .filter(|die| self.numbers[*die] == *num && !held[*die])
+                .collect();
+            let die = free
+                .iter()
+                .find(|die| self.held[**die])
+                .or(free.first())
+                .expect("validated above");
+            held[*die] = true;
+        }
+        return Ok(held);
+    }
+
+    /// Choose the numbers (not their indices) to keep for the next throw.
+    pub fn keep(&mut self, kept_numbers: Vec<i32>) -> Result<(), String> {
+        self.check_keep()?;
+        let held = self.held_for(&kept_numbers)?;
+        return self.hold(held);
+    }
+
+    /// Write the current numbers to a column of the current player's score card and
+    /// return the points awarded (without the column multiplier).
+    pub fn choose_category(&mut self, column: usize, score_t: ScoreType) -> Result<i32, String> {
+        if self.n_throws == 0 {
+            return Err(String::from("the dice have not been thrown yet"));
+        }
+        if self.scored {
+            return Err(String::from("a score was already written this turn"));
+        }
+        self.check_box(column, score_t)?;
+        self.record(Action::Score(column, score_t));
+        let sheet = self.score_cards[self.current_player]
+            .column_mut(column)
+            .expect("checked above");
+        update_score_sheet(sheet, score_t, &self.numbers, &self.rules);
+        self.scored = true;
+        return Ok(sheet.get_score(score_t).unwrap_or_default());
+    }
+
+    /// Hand the dice to the next player once the current one has written a score.
+    pub fn advance_player(&mut self) -> Result<(), String> {
+        if !self.scored {
+            return Err(String::from("no score written this turn"));
+        }
+        if self.rules.carry_over_throws {
+            self.saved_throws[self.current_player] = self.throws_left();
+        }
+        self.numbers.clear();
+        self.held.fill(false);
+        self.n_throws = 0;
+        self.announcement = None;
+        self.scored = false;
+        self.current_player += 1;
+        if self.current_player == self.players.len() {
+            self.current_player = 0;
+            self.turn += 1;
+        }
+        return Ok(());
+    }
+
+    /// The game as JSON, see `from_json`.
+    pub fn to_json(&self) -> Result<String, String> {
+        let saved = SavedGame {
+            version: SAVE_VERSION,
+            players: self.players.clone(),
+            score_cards: self.score_cards.clone(),
+            rules: self.rules.clone(),
+            turn: self.turn,
+            current_player: self.current_player,
+            numbers: self.numbers.clone(),
+            held: self.held.clone(),
+            n_throws: self.n_throws,
+            saved_throws: self.saved_throws.clone(),
+            announcement: self.announcement,
+            scored: self.scored,
+            history: self.history.clone(),
+            dice: self.dice.position(),
+        };
+        return serde_json::to_string_pretty(&saved).map_err(|e| e.to_string());
+    }
+
+    /// Restore a game saved with `to_json`. Seeded dice continue with the same numbers,
+    /// other dice sources cannot be saved and are replaced by new random dice.
+    pub fn from_json(json: &str) -> Result<GameState, String> {
+        let saved: SavedGame =
+            serde_json::from_str(json).map_err(|e| format!("invalid save file: {e}"))?;
+        if saved.version != SAVE_VERSION {
+            return Err(format!(
+                "save file version {} is not supported",
+                saved.version
+            ));
+        }
+        saved
+            .rules
+            .validate()
+            .map_err(|e| format!("invalid save file: {e}"))?;
+        let n_players = saved.players.len();
+        if n_players == 0
+            || saved.score_cards.len() != n_players
+            || saved.saved_throws.len() != n_players
+            || saved.current_player >= n_players
+            || saved
+                .score_cards
+                .iter()
+                .any(|card| card.columns().len() != saved.rules.columns.len())
+        {
+            return Err(String::from(
+                "invalid save file: players and score cards do not match",
+            ));
+        }
+        // saves from before dice were held by position are only written between throws
+        let held = match saved.held.is_empty() {
+            true => vec![false; saved.rules.n_dice],
+            false => saved.held,
+        };
+        if held.len() != saved.rules.n_dice {
+            return Err(String::from("invalid save file: wrong number of held dice"));
+        }
+        let rules = &saved.rules;
+        let thrown = !saved.numbers.is_empty();
+        if (thrown && saved.numbers.len() != rules.n_dice)
+            || saved
+                .numbers
+                .iter()
+                .any(|num| *num < 1 || *num > rules.n_faces)
+            || thrown != (saved.n_throws > 0)
+            || (!thrown && held.contains(&true))
+        {
+            return Err(String::from(
+                "invalid save file: the dice do not fit the rules",
+            ));
+        }
+        // carried over throws only come from earlier turns that used at least one throw
+        let max_saved_throws = match rules.carry_over_throws {
+            true => (rules.n_throws - 1) * rules.n_turns(),
+            false => 0,
+        };
+        if saved
+            .saved_throws
+            .iter()
+            .any(|throws| *throws > max_saved_throws)
+        {
+            return Err(String::from(
+                "invalid save file: carried over throws out of range",
+            ));
+        }
+        if saved
+            .score_cards
+            .iter()
+            .flat_map(|card| card.columns())
+            .any(|sheet| {
+                sheet
+                    .written()
+                    .any(|score_t| !rules.categories.contains(&score_t))
+            })
+        {
+            return Err(String::from(
+                "invalid save file: score cards contain boxes the rules do not have",
+            ));
+        }
+        if let Some((column, score_t)) = saved.announcement {
+            let sheet = saved.score_cards[saved.current_player]
+                .columns()
+                .get(column);
+            let announceable = rules.columns.get(column).map(|c| c.order)
+                == Some(ColumnOrder::Announced)
+                && sheet
+                    .is_some_and(|sheet| sheet.is_fillable(score_t, ColumnOrder::Announced, rules));
+            if !thrown || !announceable {
+                return Err(String::from(
+                    "invalid save file: the announced box cannot be written",
+                ));
+            }
+        }
+        if saved.n_throws > rules.n_throws + saved.saved_throws[saved.current_player]
+            || saved.turn == 0
+            || saved.turn > rules.n_turns() + 1
+        {
+            return Err(String::from(
+                "invalid save file: turn or throws out of range",
+            ));
+        }
+        let dice = match saved.dice {
+            Some((seed, n_rolls)) => Dice::new(Box::new(SeededDiceSource::at_position(
+                seed,
+                n_rolls,
+                saved.rules.n_faces,
+            ))),
+            None => Dice::seeded(Dice::random_seed()),
+        };
+        return Ok(GameState {
+            players: saved.players,
+            score_cards: saved.score_cards,
+            dice,
+            rules: saved.rules,
+            turn: saved.turn,
+            current_player: saved.current_player,
+            numbers: saved.numbers,
+            held,
+            n_throws: saved.n_throws,
+            saved_throws: saved.saved_throws,
+            announcement: saved.announcement,
+            scored: saved.scored,
+            history: saved.history,
+        });
+    }
+
+    /// Write the game to a file. The file is replaced atomically, so an interrupted save
+    /// keeps the previous content.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = self.to_json()?;
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        fs::write(&temporary, json)
+            .and_then(|_| fs::rename(&temporary, path))
+            .map_err(|e| format!("could not write {}: {e}", path.display()))?;
+        return Ok(());
+    }
+
+    pub fn load(path: &Path) -> Result<GameState, String> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| format!("could not read {}: {e}", path.display()))?;
+        return GameState::from_json(&json);
+    }
+
+    /// The seed of the dice if they are seeded, e.g. to derive the seeds of bots.
+    pub fn dice_seed(&self) -> Option<u64> {
+        return self.dice.position().map(|(seed, _)| seed);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        return self.turn > self.rules.n_turns();
+    }
+
+    /// Final (or intermediate) sums of all players in seating order.
+    pub fn results(&self) -> Vec<(String, i32)> {
+        return self
+            .players
+            .iter()
+            .zip(&self.score_cards)
+            .map(|(player, card)| (player.clone(), card.sum(&self.rules)))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Column;
+
+    fn two_player_game() -> GameState {
+        return GameState::new(
+            vec![String::from("alice"), String::from("bob")],
+            Dice::seeded(1),
+            Ruleset::kniffel(),
+        );
+    }
+
+    #[test]
+    fn turn_sequence() {
+        let mut game = two_player_game();
+        assert_eq!(game.throws_left(), 3);
+        assert_eq!(game.roll().unwrap().len(), 5);
+        let keep = game.numbers()[..2].to_vec();
+        game.keep(keep.clone()).unwrap();
+        game.roll().unwrap();
+        assert_eq!(game.numbers()[..2], keep[..]);
+        game.roll().unwrap();
+        assert_eq!(game.throws_left(), 0);
+        assert!(game.roll().is_err());
+        assert!(game.keep(vec![]).is_err());
+
+        let points = game.choose_category(0, ScoreType::Chance).unwrap();
+        assert_eq!(points, game.numbers().iter().sum::<i32>());
+        assert!(game.choose_category(0, ScoreType::Ones).is_err());
+
+        game.advance_player().unwrap();
+        assert_eq!(game.current_player(), 1);
+        assert_eq!(game.current_player_name(), "bob");
+        assert_eq!(game.turn(), 1);
+        assert!(game.numbers().is_empty());
+    }
+
+    #[test]
+    fn scripted_turn() {
+        let mut game = GameState::new(
+            vec![String::from("alice")],
+            Dice::scripted(vec![4, 4, 1, 2, 6, 3, 5, 4, 4]),
+            Ruleset::kniffel(),
+        );
+        assert_eq!(game.roll().unwrap(), [4, 4, 1, 2, 6]);
+        game.keep(vec![4, 4]).unwrap();
+        assert_eq!(game.roll().unwrap(), [4, 4, 3, 5, 4]);
+        game.keep(vec![4, 4, 4, 3]).unwrap();
+        assert_eq!(game.held(), [true, true, true, false, true]);
+        assert_eq!(game.roll().unwrap(), [4, 4, 3, 4, 4]);
+        assert_eq!(game.choose_category(0, ScoreType::Fours), Ok(16));
+    }
+
+    #[test]
+    fn hold_by_position() {
+        let mut game = GameState::new(
+            vec![String::from("alice")],
+            Dice::scripted(vec![3, 5, 3, 1, 6, 2, 3, 4, 6]),
+            Ruleset::kniffel(),
+        );
+        game.roll().unwrap();
+        assert!(game.hold(vec![true, false]).is_err());
+        game.hold(vec![false, false, true, false, false]).unwrap();
+        assert_eq!(game.kept_numbers(), [3]);
+        assert_eq!(game.roll().unwrap(), [2, 3, 3, 4, 6]);
+        assert_eq!(game.held(), [false, false, true, false, false]);
+        // the held three is kept in place, the other one is the first free three
+        assert_eq!(
+            game.held_for(&[3, 3, 6]).unwrap(),
+            [false, true, true, false, true]
+        );
+        assert!(game.held_for(&[2, 2]).is_err());
+        assert_eq!(game.history()[0].action, Action::Keep(vec![3]));
+    }
+
+    #[test]
+    fn history() {
+        let mut game = GameState::new(
+            vec![String::from("alice"), String::from("bob")],
+            Dice::scripted(vec![4, 4, 1, 2, 6, 3, 5, 4, 1, 1, 1, 1, 1]),
+            Ruleset::kniffel(),
+        );
+        game.roll().unwrap();
+        game.keep(vec![4, 4]).unwrap();
+        assert!(game.keep(vec![5]).is_err());
+        game.roll().unwrap();
+        game.choose_category(0, ScoreType::Fours).unwrap();
+        game.advance_player().unwrap();
+        game.roll().unwrap();
+        assert!(game.choose_category(0, ScoreType::LargeStraight).is_ok());
+
+        let history = game.history();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0].action, Action::Keep(vec![4, 4]));
+        assert_eq!(history[0].numbers, [4, 4, 1, 2, 6]);
+        assert_eq!(history[0].throws_left, 2);
+        assert_eq!(history[1].action, Action::Score(0, ScoreType::Fours));
+        assert_eq!(history[1].numbers, [4, 4, 3, 5, 4]);
+        assert_eq!(history[1].held, [true, true, false, false, false]);
+        assert!(!history[1].score_card.columns()[0].is_already_written(ScoreType::Fours));
+        assert_eq!((history[2].player, history[2].turn), (1, 1));
+    }
+
+    #[test]
+    fn carry_over_throws() {
+        let mut game = GameState::new(
+            vec![String::from("alice")],
+            Dice::scripted(vec![1, 2, 3, 4, 5, 6, 5, 5, 5, 5, 5, 1, 2]),
+            Ruleset::maxi_yatzy(),
+        );
+        assert_eq!(game.roll().unwrap(), [1, 2, 3, 4, 5, 6]);
+        assert_eq!(game.choose_category(0, ScoreType::FullStraight), Ok(21));
+        game.advance_player().unwrap();
+        assert_eq!(game.throws_left(), 5);
+
+        game.roll().unwrap();
+        game.keep(vec![5, 5, 5, 5, 5]).unwrap();
+        game.roll().unwrap();
+        assert_eq!(game.throws_left(), 3);
+        assert_eq!(game.choose_category(0, ScoreType::FiveOfAKind), Ok(25));
+        game.advance_player().unwrap();
+        assert_eq!(game.throws_left(), 6);
+    }
+
+    #[test]
+    fn throws_are_not_carried_over_by_default() {
+        let mut game = GameState::new(
+            vec![String::from("alice")],
+            Dice::seeded(2),
+            Ruleset::kniffel(),
+        );
+        game.roll().unwrap();
+        game.choose_category(0, ScoreType::Chance).unwrap();
+        game.advance_player().unwrap();
+        assert_eq!(game.throws_left(), 3);
+    }
+
+    #[test]
+    fn columns() {
+        let mut game = GameState::new(
+            vec![String::from("alice")],
+            Dice::scripted(vec![6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 1, 2, 3, 4, 5]),
+            Ruleset::kniffel().with_columns(2),
+        );
+        assert_eq!(game.rules().n_turns(), 26);
+        game.roll().unwrap();
+        assert_eq!(game.choose_category(1, ScoreType::Yahtzee), Ok(50));
+        game.advance_player().unwrap();
+        game.roll().unwrap();
+        assert!(game.choose_category(1, ScoreType::Yahtzee).is_err());
+        assert!(game.choose_category(2, ScoreType::Yahtzee).is_err());
+        assert_eq!(game.choose_category(0, ScoreType::Yahtzee), Ok(50));
+        game.advance_player().unwrap();
+        game.roll().unwrap();
+        assert_eq!(game.choose_category(1, ScoreType::Chance), Ok(15));
+        assert_eq!(game.results()[0].1, 50 + 2 * (50 + 15));
+    }
+
+    #[test]
+    fn invalid_saves() {
+        let mut game = two_player_game();
+        game.roll().unwrap();
+        let json: serde_json::Value = serde_json::from_str(&game.to_json().unwrap()).unwrap();
+        let with = |field: &str, value: serde_json::Value| {
+            let mut changed = json.clone();
+            changed[field] = value;
+            return GameState::from_json(&changed.to_string());
+        };
+        assert!(with("turn", serde_json::json!(1)).is_ok());
+        let invalid = [
+            ("numbers", serde_json::json!([1, 2, 3])),
+            ("numbers", serde_json::json!([1, 2, 3, 4, 7])),
+            ("numbers", serde_json::json!([])),
+            ("n_throws", serde_json::json!(0)),
+            ("n_throws", serde_json::json!(4)),
+            ("turn", serde_json::json!(0)),
+            ("turn", serde_json::json!(15)),
+            ("held", serde_json::json!([true, false])),
+            ("saved_throws", serde_json::json!([0, 2])),
+            ("announcement", serde_json::json!([1, "Chance"])),
+        ];
+        for (field, value) in invalid {
+            let error = match with(field, value.clone()) {
+                Ok(_) => panic!("{field} {value} was accepted"),
+                Err(e) => e,
+            };
+            assert!(error.starts_with("invalid save file"), "{error}");
+        }
+        let mut unknown_box = json.clone();
+        unknown_box["score_cards"][0]["columns"][0]["scores"]["FiveOfAKind"] =
+            serde_json::json!(25);
+        let mut filled_announcement = json.clone();
+        filled_announcement["rules"]["columns"][0]["order"] = serde_json::json!("Announced");
+        filled_announcement["score_cards"][0]["columns"][0]["scores"]["Chance"] =
+            serde_json::json!(20);
+        filled_announcement["announcement"] = serde_json::json!([0, "Chance"]);
+        let mut open_announcement = filled_announcement.clone();
+        open_announcement["announcement"] = serde_json::json!([0, "Sixes"]);
+        assert!(GameState::from_json(&open_announcement.to_string()).is_ok());
+        for invalid in [unknown_box, filled_announcement] {
+            let error = match GameState::from_json(&invalid.to_string()) {
+                Ok(_) => panic!("{invalid} was accepted"),
+                Err(e) => e,
+            };
+            assert!(error.starts_with("invalid save file"), "{error}");
+        }
+        let mut no_throws = json.clone();
+        no_throws["rules"]["n_throws"] = serde_json::json!(0);
+        assert!(GameState::from_json(&no_throws.to_string()).is_err());
+    }
+
+    #[test]
+    fn save_and_resume() {
+        fn play_on(game: &mut GameState) {
+            // keep the first die once, then write to the first legal box
+            while !game.is_finished() {
+                if game.numbers().is_empty() {
+                    game.roll().unwrap();
+                    let first = game.numbers()[..1].to_vec();
+                    game.keep(first).unwrap();
+                    game.roll().unwrap();
+                }
+                let (column, score_t) = game.legal_boxes()[0];
+                game.choose_category(column, score_t).unwrap();
+                game.advance_player().unwrap();
+            }
+        }
+
+        let rules = Ruleset {
+            columns: vec![
+                Column {
+                    multiplier: 1,
+                    order: ColumnOrder::TopDown,
+                },
+                Column::free(2),
+            ],
+            ..Ruleset::kniffel()
+        };
+        let players = vec![String::from("alice"), String::from("bob")];
+        let mut game = GameState::new(players, Dice::seeded(3), rules);
+        for _ in 0..7 {
+            game.roll().unwrap();
+            let (column, score_t) = game.legal_boxes()[0];
+            game.choose_category(column, score_t).unwrap();
+            game.advance_player().unwrap();
+        }
+        game.roll().unwrap();
+        let mut resumed = GameState::from_json(&game.to_json().unwrap()).unwrap();
+        assert_eq!(resumed.current_player(), 1);
+        assert_eq!(resumed.numbers(), game.numbers());
+        assert_eq!(resumed.throws_left(), 2);
+        assert_eq!(resumed.history().len(), 7);
+        assert_eq!(resumed.dice_seed(), Some(3));
+
+        play_on(&mut game);
+        play_on(&mut resumed);
+        assert_eq!(resumed.results(), game.results());
+
+        assert!(GameState::from_json("{}").is_err());
+        let path = std::env::temp_dir().join(format!("kniffel-test-{}.json", std::process::id()));
+        game.save(&path).unwrap();
+        assert_eq!(GameState::load(&path).unwrap().results(), game.results());
+        fs::remove_file(&path).unwrap();
+        assert!(GameState::load(&path).is_err());
+        let newer = game.to_json().unwrap().replacen(
+            &format!("\"version\": {SAVE_VERSION}"),
+            "\"version\": 999",
+            1,
+        );
+        match GameState::from_json(&newer) {
+            Err(e) => assert!(e.contains("not supported")),
+            Ok(_) => panic!("a newer save file version was accepted"),
+        }
+    }
+
+    #[test]
+    fn ordered_columns() {
+        let mut game = GameState::new(
+            vec![String::from("alice")],
+            Dice::seeded(4),
+            Ruleset::kniffel_block(),
+        );
+        game.roll().unwrap();
+        assert!(game.check_box(0, ScoreType::Twos).is_err());
+        assert!(game.check_box(1, ScoreType::Yahtzee).is_err());
+        assert!(game.check_box(3, ScoreType::Chance).is_err());
+        let legal = game.legal_boxes();
+        assert_eq!(legal[0], (0, ScoreType::Ones));
+        assert_eq!(legal[1], (1, ScoreType::Chance));
+        assert_eq!(legal.len(), 2 + 13);
+
+        game.choose_category(1, ScoreType::Chance).unwrap();
+        game.advance_player().unwrap();
+        game.roll().unwrap();
+        assert!(game.check_box(1, ScoreType::Chance).is_err());
+        assert!(game.check_box(1, ScoreType::Yahtzee).is_ok());
+        game.choose_category(0, ScoreType::Ones).unwrap();
+        game.advance_player().unwrap();
+        game.roll().unwrap();
+        assert!(game.check_box(0, ScoreType::Ones).is_err());
+        assert!(game.check_box(0, ScoreType::Twos).is_ok());
+    }
+
+    #[test]
+    fn joker_in_ordered_columns() {
+        let rules = Ruleset {
+            columns: vec![
+                Column {
+                    multiplier: 1,
+                    order: ColumnOrder::BottomUp,
+                },
+                Column::free(1),
+            ],
+            categories: vec![
+                ScoreType::Threes,
+                ScoreType::Fours,
+                ScoreType::LargeStraight,
+                ScoreType::Yahtzee,
+            ],
+            ..Ruleset::hasbro_yahtzee()
+        };
+        let mut game = GameState::new(
+            vec![String::from("alice")],
+            Dice::scripted(vec![3; 15]),
+            rules,
+        );
+        for column in [0, 1] {
+            game.roll().unwrap();
+            game.choose_category(column, ScoreType::Yahtzee).unwrap();
+            game.advance_player().unwrap();
+        }
+        game.roll().unwrap();
+        // the free column forces the joker to Threes, the ordered one only allows its
+        // next box, which the joker fills with full points
+        assert!(game.check_box(1, ScoreType::LargeStraight).is_err());
+        assert!(game.check_box(1, ScoreType::Threes).is_ok());
+        assert!(game.check_box(0, ScoreType::Threes).is_err());
+        assert_eq!(
+            game.legal_boxes(),
+            [(0, ScoreType::LargeStraight), (1, ScoreType::Threes)]
+        );
+        assert_eq!(game.choose_category(0, ScoreType::LargeStraight), Ok(40));
+        assert_eq!(
+            game.current_score_card().columns()[0].yahtzee_bonus_count(),
+            1
+        );
+    }
+
+    #[test]
+    fn announced_column() {
+        let mut game = GameState::new(
+            vec![String::from("alice")],
+            Dice::scripted(vec![6, 6, 6, 6, 1, 6, 6, 6, 6, 6, 2, 3]),
+            Ruleset::kniffel_block(),
+        );
+        assert!(!game.can_announce());
+        assert!(game.announce(3, ScoreType::Yahtzee).is_err());
+        game.roll().unwrap();
+        assert!(game.can_announce());
+        assert!(!game.must_announce());
+        assert!(game.announce(2, ScoreType::Yahtzee).is_err());
+        game.announce(3, ScoreType::Yahtzee).unwrap();
+        assert!(!game.can_announce());
+        assert_eq!(game.legal_boxes(), vec![(3, ScoreType::Yahtzee)]);
+        assert!(game.choose_category(2, ScoreType::Sixes).is_err());
+        game.keep(vec![6, 6, 6, 6]).unwrap();
+        game.roll().unwrap();
+        assert!(!game.can_announce());
+        assert_eq!(game.choose_category(3, ScoreType::Yahtzee), Ok(50));
+        game.advance_player().unwrap();
+        assert_eq!(game.announcement(), None);
+
+        // without an announcement the column is closed for the turn
+        game.roll().unwrap();
+        game.keep(vec![6, 6, 6, 6]).unwrap();
+        game.roll().unwrap();
+        assert!(game.check_box(3, ScoreType::Sixes).is_err());
+        assert!(game.check_box(2, ScoreType::Sixes).is_ok());
+    }
+
+    #[test]
+    fn dice_and_throws_from_rules() {
+        let mut game = GameState::new(
+            vec![String::from("alice")],
+            Dice::scripted(vec![8, 7, 1, 2, 8, 8, 2]),
+            Ruleset::kniffel().with_dice(4, 8).with_throws(4),
+        );
+        assert_eq!(game.throws_left(), 4);
+        assert_eq!(game.roll().unwrap(), [8, 7, 1, 2]);
+        assert!(game.keep(vec![9]).is_err());
+        game.keep(vec![8]).unwrap();
+        assert_eq!(game.roll().unwrap(), [8, 8, 8, 2]);
+        assert_eq!(game.throws_left(), 2);
+        assert_eq!(game.choose_category(0, ScoreType::ThreeOfAKind), Ok(26));
+    }
+
+    #[test]
+    fn invalid_steps() {
+        let mut game = two_player_game();
+        assert!(game.keep(vec![]).is_err());
+        assert!(game.choose_category(0, ScoreType::Chance).is_err());
+        assert!(game.advance_player().is_err());
+
+        game.roll().unwrap();
+        assert!(game.keep(vec![7]).is_err());
+        assert!(game.keep(vec![1, 1, 1, 1, 1, 1]).is_err());
+    }
+
+    #[test]
+    fn full_game() {
+        let mut game = two_player_game();
+        while !game.is_finished() {
+            game.roll().unwrap();
+            let score_t = game.rules().categories[game.turn() - 1];
+            game.choose_category(0, score_t).unwrap();
+            game.advance_player().unwrap();
+        }
+        assert_eq!(game.turn(), 14);
+        assert!(game.roll().is_err());
+
+        let results = game.results();
+        assert_eq!(results.len(), 2);
+        for (i, (player, sum)) in results.into_iter().enumerate() {
+            assert_eq!(player, game.players()[i]);
+            assert_eq!(sum, game.score_cards()[i].sum(game.rules()));
+        }
+    }
+
+    #[test]
+    fn yacht_game() {
+        let rules = Ruleset::yacht();
+        let mut game = GameState::new(vec![String::from("alice")], Dice::seeded(3), rules.clone());
+        game.roll().unwrap();
+        assert!(game.choose_category(0, ScoreType::ThreeOfAKind).is_err());
+        for score_t in rules.categories {
+            assert!(!game.is_finished());
+            game.choose_category(0, score_t).unwrap();
+            game.advance_player().unwrap();
+            if !game.is_finished() {
+                game.roll().unwrap();
+            }
+        }
+        assert!(game.is_finished());
+        assert_eq!(game.turn(), 13);
+    }
+}
@@ -1,579 +1,1273 @@
-use rand::rngs::ThreadRng;
-use rand::Rng;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::{env, fs};
 
+pub mod analysis;
+mod bots;
+mod dice;
+mod game;
+pub mod notation;
+mod player;
+pub mod probability;
+pub mod record;
+mod rules;
+pub mod simulation;
+pub mod solver;
+pub mod tournament;
 mod utils;
 
-#[derive(Debug, Clone, Copy)]
-enum ScoreType {
+pub use bots::{create_bot, Difficulty, GreedyBot, OptimalBot, RandomBot};
+pub use dice::{Dice, DiceSource, ScriptedDiceSource, SeededDiceSource};
+pub use game::{Action, Decision, GameState};
+pub use player::{HumanPlayer, Player};
+pub use rules::{Column, ColumnOrder, KindPoints, Points, Ruleset, Straights};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ScoreType {
     Ones,
     Twos,
     Threes,
     Fours,
     Fives,
     Sixes,
+    OnePair,
+    TwoPairs,
+    ThreePairs,
     ThreeOfAKind,
     FourOfAKind,
+    FiveOfAKind,
     FullHouse,
+    Castle,
+    Tower,
     SmallStraight,
     LargeStraight,
+    FullStraight,
     Yahtzee,
     Chance,
 }
 
-#[derive(Default, Debug)]
-struct ScoreSheet {
-    ones: Option<i32>,
-    twos: Option<i32>,
-    threes: Option<i32>,
-    fours: Option<i32>,
-    fives: Option<i32>,
-    sixes: Option<i32>,
-    three_of_a_kind: Option<i32>,
-    four_of_a_kind: Option<i32>,
-    full_house: Option<i32>,
-    small_straight: Option<i32>,
-    large_straight: Option<i32>,
-    yahtzee: Option<i32>,
-    chance: Option<i32>,
-}
-
-impl ScoreSheet {
-    fn new() -> ScoreSheet {
-        ScoreSheet {
-            ..Default::default()
-        }
-    }
-    fn sum(&self) -> i32 {
-        let upper_sum = self.ones.unwrap_or_default()
-            + self.twos.unwrap_or_default()
-            + self.threes.unwrap_or_default()
-            + self.fours.unwrap_or_default()
-            + self.fives.unwrap_or_default()
-            + self.sixes.unwrap_or_default();
-        let bonus = if upper_sum >= 63 { 35 } else { 0 };
-        let lower_sum = self.three_of_a_kind.unwrap_or_default()
-            + self.four_of_a_kind.unwrap_or_default()
-            + self.full_house.unwrap_or_default()
-            + self.small_straight.unwrap_or_default()
-            + self.large_straight.unwrap_or_default()
-            + self.yahtzee.unwrap_or_default()
-            + self.chance.unwrap_or_default();
-        return upper_sum + bonus + lower_sum;
-    }
-
-    fn get_score(&self, score_t: ScoreType) -> Option<i32> {
-        match score_t {
-            ScoreType::Ones => return self.ones,
-            ScoreType::Twos => return self.twos,
-            ScoreType::Threes => return self.threes,
-            ScoreType::Fours => return self.fours,
-            ScoreType::Fives => return self.fives,
-            ScoreType::Sixes => return self.sixes,
-            ScoreType::ThreeOfAKind => return self.three_of_a_kind,
-            ScoreType::FourOfAKind => return self.four_of_a_kind,
-            ScoreType::FullHouse => return self.full_house,
-            ScoreType::SmallStraight => return self.small_straight,
-            ScoreType::LargeStraight => return self.large_straight,
-            ScoreType::Yahtzee => return self.yahtzee,
-            ScoreType::Chance => return self.chance,
-        }
-    }
-
-    fn is_already_written(&self, score_t: ScoreType) -> bool {
-        match self.get_score(score_t) {
-            Some(_) => return true,
-            None => return false,
-        }
-    }
-}
-
-fn print_all_score_sheets(player_sheets: &mut HashMap<String, ScoreSheet>) {
-    // TODO: Sort out how this function can be called with immutable refs in the main game loop.
-    let score_type_names = vec![
-        "Ones",
-        "Twos",
-        "Threes",
-        "Fours",
-        "Fives",
-        "Sixes",
-        "Three of a Kind",
-        "Four of a Kind",
-        "Full House",
-        "Small Straight",
-        "Large Straight",
-        "Yahtzee",
-        "Chance",
-    ];
-    let score_types = vec![
+impl ScoreType {
+    pub const ALL: [ScoreType; 20] = [
         ScoreType::Ones,
         ScoreType::Twos,
         ScoreType::Threes,
         ScoreType::Fours,
         ScoreType::Fives,
         ScoreType::Sixes,
+        ScoreType::OnePair,
+        ScoreType::TwoPairs,
+        ScoreType::ThreePairs,
         ScoreType::ThreeOfAKind,
         ScoreType::FourOfAKind,
+        ScoreType::FiveOfAKind,
         ScoreType::FullHouse,
+        ScoreType::Castle,
+        ScoreType::Tower,
         ScoreType::SmallStraight,
         ScoreType::LargeStraight,
+        ScoreType::FullStraight,
         ScoreType::Yahtzee,
         ScoreType::Chance,
     ];
 
-    // Print the header row: Player names as columns
-    print!("{:<25}", "");
-    for player_name in player_sheets.keys() {
-        print!("{:<15}", player_name);
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScoreType::Ones => return "Ones",
+            ScoreType::Twos => return "Twos",
+            ScoreType::Threes => return "Threes",
+            ScoreType::Fours => return "Fours",
+            ScoreType::Fives => return "Fives",
+            ScoreType::Sixes => return "Sixes",
+            ScoreType::OnePair => return "One Pair",
+            ScoreType::TwoPairs => return "Two Pairs",
+            ScoreType::ThreePairs => return "Three Pairs",
+            ScoreType::ThreeOfAKind => return "Three of a Kind",
+            ScoreType::FourOfAKind => return "Four of a Kind",
+            ScoreType::FiveOfAKind => return "Five of a Kind",
+            ScoreType::FullHouse => return "Full House",
+            ScoreType::Castle => return "Castle",
+            ScoreType::Tower => return "Tower",
+            ScoreType::SmallStraight => return "Small Straight",
+            ScoreType::LargeStraight => return "Large Straight",
+            ScoreType::FullStraight => return "Full Straight",
+            ScoreType::Yahtzee => return "Yahtzee",
+            ScoreType::Chance => return "Chance",
+        }
     }
-    println!();
 
-    // Print each category with the corresponding score for each player
-    for (i, category) in score_type_names.iter().enumerate() {
-        print!("{:<25}", category);
-        for sheet in player_sheets.values() {
-            print!(
-                "{:<15}",
-                sheet
-                    .get_score(score_types[i])
-                    .map_or(" ".to_string(), |v| v.to_string())
-            );
+    /// What a player types to pick this score type.
+    pub fn abbreviation(&self) -> &'static str {
+        match self {
+            ScoreType::Ones => return "1",
+            ScoreType::Twos => return "2",
+            ScoreType::Threes => return "3",
+            ScoreType::Fours => return "4",
+            ScoreType::Fives => return "5",
+            ScoreType::Sixes => return "6",
+            ScoreType::OnePair => return "1p",
+            ScoreType::TwoPairs => return "2p",
+            ScoreType::ThreePairs => return "3p",
+            ScoreType::ThreeOfAKind => return "tk",
+            ScoreType::FourOfAKind => return "fk",
+            ScoreType::FiveOfAKind => return "5k",
+            ScoreType::FullHouse => return "fh",
+            ScoreType::Castle => return "ca",
+            ScoreType::Tower => return "to",
+            ScoreType::SmallStraight => return "ss",
+            ScoreType::LargeStraight => return "ls",
+            ScoreType::FullStraight => return "fs",
+            ScoreType::Yahtzee => return "y",
+            ScoreType::Chance => return "c",
         }
+    }
 
-        println!();
+    pub fn is_upper(&self) -> bool {
+        return ScoreType::ALL[..6].contains(self);
+    }
+
+    /// The upper half score type that counts the given number, if there is one.
+    pub fn upper_for(number: i32) -> Option<ScoreType> {
+        if !(1..=6).contains(&number) {
+            return None;
+        }
+        return Some(ScoreType::ALL[(number - 1) as usize]);
     }
 }
 
-struct Dice {
-    rng: ThreadRng,
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct ScoreSheet {
+    scores: BTreeMap<ScoreType, i32>,
+    yahtzee_bonus_count: i32,
 }
 
-impl Dice {
-    fn new() -> Dice {
-        Dice {
-            rng: rand::thread_rng(),
+impl ScoreSheet {
+    pub fn new() -> ScoreSheet {
+        ScoreSheet {
+            ..Default::default()
+        }
+    }
+
+    pub fn upper_sum(&self) -> i32 {
+        return self
+            .scores
+            .iter()
+            .filter(|(score_t, _)| score_t.is_upper())
+            .map(|(_, points)| points)
+            .sum();
+    }
+
+    pub fn upper_bonus(&self, rules: &Ruleset) -> i32 {
+        if rules.upper_bonus > 0 && self.upper_sum() >= rules.upper_bonus_threshold {
+            return rules.upper_bonus;
+        }
+        return 0;
+    }
+
+    pub fn sum(&self, rules: &Ruleset) -> i32 {
+        let lower_sum: i32 = self
+            .scores
+            .iter()
+            .filter(|(score_t, _)| !score_t.is_upper())
+            .map(|(_, points)| points)
+            .sum();
+        return self.upper_sum() + self.upper_bonus(rules) + lower_sum + self.yahtzee_bonus(rules);
+    }
+
+    /// Number of extra Yahtzees thrown after the Yahtzee box was filled with full points.
+    pub fn yahtzee_bonus_count(&self) -> i32 {
+        return self.yahtzee_bonus_count;
+    }
+
+    pub fn yahtzee_bonus(&self, rules: &Ruleset) -> i32 {
+        return rules.yahtzee_bonus * self.yahtzee_bonus_count;
+    }
+
+    pub fn get_score(&self, score_t: ScoreType) -> Option<i32> {
+        return self.scores.get(&score_t).copied();
+    }
+
+    fn set_score(&mut self, score_t: ScoreType, value: i32) {
+        self.scores.insert(score_t, value);
+    }
+
+    /// The boxes that have been written, in the order of `ScoreType`.
+    pub fn written(&self) -> impl Iterator<Item = ScoreType> + '_ {
+        return self.scores.keys().copied();
+    }
+
+    pub fn is_already_written(&self, score_t: ScoreType) -> bool {
+        match self.get_score(score_t) {
+            Some(_) => return true,
+            None => return false,
+        }
+    }
+
+    /// Whether the box is part of the rules, still open and may be filled now in a column
+    /// with the given order. Announcements and joker rules are not considered here.
+    pub fn is_fillable(&self, score_t: ScoreType, order: ColumnOrder, rules: &Ruleset) -> bool {
+        if !rules.categories.contains(&score_t) || self.is_already_written(score_t) {
+            return false;
+        }
+        let mut open = rules
+            .categories
+            .iter()
+            .filter(|category| !self.is_already_written(**category));
+        match order {
+            ColumnOrder::Free | ColumnOrder::Announced => return true,
+            ColumnOrder::TopDown => return open.next() == Some(&score_t),
+            ColumnOrder::BottomUp => return open.next_back() == Some(&score_t),
         }
     }
 
-    fn gen_numbers(&mut self, n: usize) -> Vec<i32> {
-        let mut res = Vec::with_capacity(n);
-        for _i in 0..n {
-            res.push(self.rng.gen_range(1..=6));
+    /// A copy of the sheet with the numbers written to `score_t`, e.g. to evaluate a
+    /// hypothetical decision.
+    pub fn with_score(&self, score_t: ScoreType, numbers: &[i32], rules: &Ruleset) -> ScoreSheet {
+        let mut sheet = self.clone();
+        update_score_sheet(&mut sheet, score_t, numbers, rules);
+        return sheet;
+    }
+
+    /// A sheet with the given points written, e.g. to analyze a position of another game.
+    pub fn from_scores(scores: &[(ScoreType, i32)]) -> ScoreSheet {
+        let mut sheet = ScoreSheet::new();
+        for (score_t, points) in scores {
+            sheet.set_score(*score_t, *points);
         }
-        res.sort();
-        return res;
+        return sheet;
+    }
+
+    pub fn is_full(&self, rules: &Ruleset) -> bool {
+        return rules
+            .categories
+            .iter()
+            .all(|score_t| self.is_already_written(*score_t));
     }
 }
 
-fn decide_keep_dice(numbers: &Vec<i32>) -> Vec<i32> {
-    // return the numbers (not their indices) you want to keep
-    println!("Your numbers are {numbers:?}");
-    println!("Enter the numbers you want to keep and press enter. Separate numbers by whitespace. Anything not an integer will be ignored. Press enter without any numbers if you want to reroll all dice");
+/// All columns of one player.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoreCard {
+    columns: Vec<ScoreSheet>,
+}
 
-    let mut input = String::new();
-    io::stdin()
-        .read_line(&mut input)
-        .expect("Failed to read line");
-    let keep_numbers: Vec<i32> = input
-        .trim()
-        .split_whitespace()
-        .filter_map(|s| s.parse::<i32>().ok())
-        .collect();
-    return keep_numbers;
+impl ScoreCard {
+    pub fn new(rules: &Ruleset) -> ScoreCard {
+        ScoreCard {
+            columns: rules.columns.iter().map(|_| ScoreSheet::new()).collect(),
+        }
+    }
+
+    pub fn columns(&self) -> &[ScoreSheet] {
+        return &self.columns;
+    }
+
+    fn column_mut(&mut self, column: usize) -> Option<&mut ScoreSheet> {
+        return self.columns.get_mut(column);
+    }
+
+    /// Sum of all columns, each with its multiplier.
+    pub fn sum(&self, rules: &Ruleset) -> i32 {
+        return self
+            .columns
+            .iter()
+            .zip(&rules.columns)
+            .map(|(sheet, column)| column.multiplier * sheet.sum(rules))
+            .sum();
+    }
 }
 
-fn decide_scoresheet_update(numbers: &Vec<i32>) -> Result<ScoreType, String> {
-    println!("your numbers are {numbers:?}");
-    println!("pick score type to write to. For upper half type 1-6. For lower half type one of fh, tk, fk, ss, ls, y, c. If your numbers do not fulfil the shape criterion, the score type will be scratched");
-    let mut input = String::new();
+static COLOR: AtomicBool = AtomicBool::new(false);
 
-    io::stdin()
-        .read_line(&mut input)
-        .expect("Failed to read line");
+/// Enable or disable colored console output, it is off by default.
+pub fn set_color(enabled: bool) {
+    COLOR.store(enabled, Ordering::Relaxed);
+}
+
+fn paint(text: &str, code: &str) -> String {
+    // ANSI escape code around the text if colors are enabled
+    if !COLOR.load(Ordering::Relaxed) {
+        return String::from(text);
+    }
+    return format!("\x1b[{code}m{text}\x1b[0m");
+}
+
+fn highlight(text: &str) -> String {
+    return paint(text, "1;36");
+}
+
+fn warning(text: &str) -> String {
+    return paint(text, "31");
+}
+
+fn print_all_score_sheets(game: &GameState) {
+    let rules = game.rules();
+    let sheets: Vec<&ScoreSheet> = game
+        .score_cards()
+        .iter()
+        .flat_map(|card| card.columns())
+        .collect();
+
+    // Print the header row: Player names (and column multipliers) as columns
+    print!("{:<25}", "");
+    for player_name in game.players() {
+        if rules.columns.len() == 1 {
+            print!("{:<15}", player_name);
+            continue;
+        }
+        for column in &rules.columns {
+            print!("{:<15}", format!("{player_name} {}", column.label()));
+        }
+    }
+    println!();
+
+    // Print each category with the corresponding score for each player
+    for category in &rules.categories {
+        print!("{:<25}", category.name());
+        for sheet in &sheets {
+            print!(
+                "{:<15}",
+                sheet
+                    .get_score(*category)
+                    .map_or(" ".to_string(), |v| v.to_string())
+            );
+        }
 
-    match input.to_lowercase().trim() {
-        "1" => return Ok(ScoreType::Ones),
-        "2" => return Ok(ScoreType::Twos),
-        "3" => return Ok(ScoreType::Threes),
-        "4" => return Ok(ScoreType::Fours),
-        "5" => return Ok(ScoreType::Fives),
-        "6" => return Ok(ScoreType::Sixes),
-        "tk" => return Ok(ScoreType::ThreeOfAKind),
-        "fk" => return Ok(ScoreType::FourOfAKind),
-        "fh" => return Ok(ScoreType::FullHouse),
-        "ss" => return Ok(ScoreType::SmallStraight),
-        "ls" => return Ok(ScoreType::LargeStraight),
-        "y" => return Ok(ScoreType::Yahtzee),
-        "c" => return Ok(ScoreType::Chance),
-        _ => return Err(input),
-    }
-}
-
-fn validate_kept_numbers(kept_numbers: &Vec<i32>, numbers: &Vec<i32>) -> Result<(), String> {
+        println!();
+
+        // the upper bonus goes right below the last box of the upper half
+        let last_upper = rules
+            .categories
+            .iter()
+            .rev()
+            .find(|score_t| score_t.is_upper());
+        if rules.upper_bonus > 0 && last_upper == Some(category) {
+            print!("{:<25}", "Bonus");
+            for sheet in &sheets {
+                print!("{:<15}", sheet.upper_bonus(rules));
+            }
+            println!();
+        }
+    }
+    if rules.yahtzee_bonus > 0 {
+        print!("{:<25}", "Yahtzee Bonus");
+        for sheet in &sheets {
+            print!("{:<15}", sheet.yahtzee_bonus(rules));
+        }
+        println!();
+    }
+}
+
+fn validate_kept_numbers(
+    kept_numbers: &[i32],
+    numbers: &[i32],
+    n_faces: i32,
+) -> Result<(), String> {
     if kept_numbers.len() > numbers.len() {
         return Err(String::from("picked too many numbers"));
     };
     for num in kept_numbers {
-        if *num > 6 || *num < 1 {
-            return Err(String::from(format!("Invalid number {num}")));
+        if *num > n_faces || *num < 1 {
+            return Err(format!("Invalid number {num}"));
         };
     }
-    let unique_kept = utils::count_unique_elements(&kept_numbers);
-    let unique_nums = utils::count_unique_elements(&numbers);
+    let unique_kept = utils::count_unique_elements(kept_numbers);
+    let unique_nums = utils::count_unique_elements(numbers);
 
     for (kept_num, count) in &unique_kept {
         let available = unique_nums.get(kept_num).unwrap_or(&0);
         if count > available {
-            return Err(String::from(format!(
+            return Err(format!(
                 "Picked {count} {kept_num}'s but you only have {available}"
-            )));
+            ));
         }
     }
 
     return Ok(());
 }
 
-fn detect_large_straight(vec: &Vec<i32>) -> bool {
-    let mut vec_mut: Vec<i32> = vec.clone();
-    vec_mut.sort();
-    return utils::vecs_elementwise_equal(&vec_mut, &Vec::from([1, 2, 3, 4, 5]))
-        || utils::vecs_elementwise_equal(&vec_mut, &Vec::from([2, 3, 4, 5, 6]));
+fn longest_run(vec: &[i32]) -> usize {
+    // length of the longest sequence of consecutive numbers among the dice
+    let unique_counts = utils::count_unique_elements(vec);
+    let mut unique_numbers: Vec<i32> = unique_counts.into_keys().collect();
+    unique_numbers.sort();
+
+    let mut longest = 0;
+    let mut current = 0;
+    for (i, num) in unique_numbers.iter().enumerate() {
+        if i > 0 && *num == unique_numbers[i - 1] + 1 {
+            current += 1;
+        } else {
+            current = 1;
+        }
+        longest = longest.max(current);
+    }
+    return longest;
 }
 
-fn detect_small_straight(vec: &Vec<i32>) -> bool {
+fn detect_large_straight(vec: &[i32]) -> bool {
+    return longest_run(vec) >= 5;
+}
+
+fn detect_small_straight(vec: &[i32]) -> bool {
     //allow large straight too
+    return longest_run(vec) >= 4;
+}
+
+fn detect_fixed_straight(vec: &[i32], straight: &[i32]) -> bool {
+    return straight.iter().all(|num| vec.contains(num));
+}
+
+/// Whether the numbers are a small straight under the ruleset's straights.
+fn is_small_straight(numbers: &[i32], rules: &Ruleset) -> bool {
+    match rules.straights {
+        Straights::Runs => return detect_small_straight(numbers),
+        Straights::Fixed => return detect_fixed_straight(numbers, &[1, 2, 3, 4, 5]),
+    }
+}
+
+/// Whether the numbers are a large straight under the ruleset's straights.
+fn is_large_straight(numbers: &[i32], rules: &Ruleset) -> bool {
+    match rules.straights {
+        Straights::Runs => return detect_large_straight(numbers),
+        Straights::Fixed => return detect_fixed_straight(numbers, &[2, 3, 4, 5, 6]),
+    }
+}
+
+fn detect_full_house(vec: &[i32]) -> Option<(i32, i32)> {
+    // highest triple and highest pair of a different number, if there are both
+    return detect_kind_and_pair(vec, 3);
+}
+
+fn detect_kind_and_pair(vec: &[i32], kind_count: usize) -> Option<(i32, i32)> {
     let unique_counts = utils::count_unique_elements(vec);
-    let mut unique_numbers: Vec<i32> = unique_counts
+    let kind = unique_counts
+        .iter()
+        .filter(|&(_num, count)| *count >= kind_count)
+        .map(|(num, _count)| *num)
+        .max()?;
+    let pair = unique_counts
+        .iter()
+        .filter(|&(num, count)| *count >= 2 && *num != kind)
+        .map(|(num, _count)| *num)
+        .max()?;
+    return Some((kind, pair));
+}
+
+fn is_yahtzee(numbers: &[i32], rules: &Ruleset) -> bool {
+    return numbers.len() == rules.n_dice && utils::count_unique_elements(numbers).len() == 1;
+}
+
+fn joker_applies(sheet: &ScoreSheet, numbers: &[i32], rules: &Ruleset) -> bool {
+    // an additional Yahtzee after the Yahtzee box was written (with full points or 0) is a joker
+    return rules.joker_rules
+        && is_yahtzee(numbers, rules)
+        && sheet.is_already_written(ScoreType::Yahtzee);
+}
+
+/// Check that the numbers may be written to `score_t`, i.e. that the box is part of the
+/// rules and open and that the forced placement of the joker rules is respected: an
+/// additional Yahtzee has to go to the matching upper box, if that is taken to any open
+/// lower box, and only if those are all taken to any upper box.
+pub fn check_score_type(
+    sheet: &ScoreSheet,
+    score_t: ScoreType,
+    numbers: &[i32],
+    rules: &Ruleset,
+) -> Result<(), String> {
+    return check_score_type_among(sheet, score_t, numbers, rules, |_| true);
+}
+
+/// Like `check_score_type`, but the joker rules only force the Yahtzee to boxes for which
+/// `available` holds, e.g. the next box of an ordered column or the announced box.
+pub(crate) fn check_score_type_among(
+    sheet: &ScoreSheet,
+    score_t: ScoreType,
+    numbers: &[i32],
+    rules: &Ruleset,
+    available: impl Fn(ScoreType) -> bool,
+) -> Result<(), String> {
+    if !rules.categories.contains(&score_t) {
+        return Err(format!(
+            "{score_t:?} is not played with {} rules",
+            rules.name
+        ));
+    }
+    if sheet.is_already_written(score_t) {
+        return Err(format!("{score_t:?} already written"));
+    }
+    if !joker_applies(sheet, numbers, rules) {
+        return Ok(());
+    }
+
+    // a Yahtzee of a number without upper box (dice with more than six faces) skips this step
+    let matching_upper = ScoreType::upper_for(numbers[0]).filter(|upper| {
+        rules.categories.contains(upper) && !sheet.is_already_written(*upper) && available(*upper)
+    });
+    if let Some(matching_upper) = matching_upper {
+        if score_t != matching_upper {
+            return Err(format!(
+                "joker rules: the Yahtzee has to be written to {matching_upper:?}"
+            ));
+        }
+        return Ok(());
+    }
+    let lower_open = rules
+        .categories
+        .iter()
+        .any(|lower| !lower.is_upper() && !sheet.is_already_written(*lower) && available(*lower));
+    if lower_open && score_t.is_upper() {
+        return Err(String::from(
+            "joker rules: the Yahtzee has to be written to an open box of the lower half",
+        ));
+    }
+    return Ok(());
+}
+
+fn kind_points(numbers: &[i32], min_count: usize, rules: &Ruleset) -> i32 {
+    // highest number that appears at least min_count times
+    let unique_counts = utils::count_unique_elements(numbers);
+    let kind = unique_counts
+        .into_iter()
+        .filter(|&(_num, count)| count >= min_count)
+        .map(|(num, _count)| num)
+        .max();
+    match (kind, rules.kind_points) {
+        (None, _) => return 0,
+        (Some(_), KindPoints::AllDice) => return numbers.iter().sum(),
+        (Some(num), KindPoints::MatchedDice) => return num * min_count as i32,
+    }
+}
+
+fn pairs_points(numbers: &[i32], n_pairs: usize) -> i32 {
+    // the n highest different numbers that appear at least twice
+    let unique_counts = utils::count_unique_elements(numbers);
+    let mut pairs: Vec<i32> = unique_counts
         .into_iter()
-        .map(|(key, _val)| key as i32)
+        .filter(|&(_num, count)| count >= 2)
+        .map(|(num, _count)| num)
         .collect();
-    unique_numbers.sort();
+    if pairs.len() < n_pairs {
+        return 0;
+    }
+    pairs.sort();
+    return 2 * pairs.iter().rev().take(n_pairs).sum::<i32>();
+}
 
-    return utils::vecs_elementwise_equal(&unique_numbers, &Vec::from([1, 2, 3, 4, 5]))
-        || utils::vecs_elementwise_equal(&unique_numbers, &Vec::from([1, 2, 3, 4, 6]))
-        || utils::vecs_elementwise_equal(&unique_numbers, &Vec::from([2, 3, 4, 5, 6]))
-        || utils::vecs_elementwise_equal(&unique_numbers, &Vec::from([1, 3, 4, 5, 6]))
-        || utils::vecs_elementwise_equal(&unique_numbers, &Vec::from([1, 2, 3, 4]))
-        || utils::vecs_elementwise_equal(&unique_numbers, &Vec::from([2, 3, 4, 5]))
-        || utils::vecs_elementwise_equal(&unique_numbers, &Vec::from([3, 4, 5, 6]));
+fn shape_points(points: Points, sum: i32) -> i32 {
+    match points {
+        Points::Fixed(value) => return value,
+        Points::Sum => return sum,
+    }
 }
 
-fn update_score_sheet(sheet: &mut ScoreSheet, score_t: ScoreType, numbers: &Vec<i32>) {
+/// The points the numbers would earn in `score_t` on the given sheet, without writing them.
+/// The Yahtzee bonus is not included.
+pub fn potential_score(
+    sheet: &ScoreSheet,
+    score_t: ScoreType,
+    numbers: &[i32],
+    rules: &Ruleset,
+) -> i32 {
+    let joker = joker_applies(sheet, numbers, rules);
     match score_t {
-        ScoreType::Ones => {
-            sheet.ones = Some(1 * numbers.iter().filter(|&&x| x == 1).count() as i32);
-        }
-        ScoreType::Twos => {
-            sheet.twos = Some(2 * numbers.iter().filter(|&&x| x == 2).count() as i32);
-        }
-        ScoreType::Threes => {
-            sheet.threes = Some(3 * numbers.iter().filter(|&&x| x == 3).count() as i32);
-        }
-        ScoreType::Fours => {
-            sheet.fours = Some(4 * numbers.iter().filter(|&&x| x == 4).count() as i32);
+        ScoreType::Ones
+        | ScoreType::Twos
+        | ScoreType::Threes
+        | ScoreType::Fours
+        | ScoreType::Fives
+        | ScoreType::Sixes => {
+            let number = ScoreType::ALL.iter().position(|&t| t == score_t).unwrap() as i32 + 1;
+            return number * numbers.iter().filter(|&&x| x == number).count() as i32;
         }
-        ScoreType::Fives => {
-            sheet.fives = Some(5 * numbers.iter().filter(|&&x| x == 5).count() as i32);
+        ScoreType::OnePair => return pairs_points(numbers, 1),
+        ScoreType::TwoPairs => return pairs_points(numbers, 2),
+        ScoreType::ThreePairs => return pairs_points(numbers, 3),
+        ScoreType::ThreeOfAKind => return kind_points(numbers, 3, rules),
+        ScoreType::FourOfAKind => return kind_points(numbers, 4, rules),
+        ScoreType::FiveOfAKind => return kind_points(numbers, 5, rules),
+        ScoreType::FullHouse => {
+            if joker || (rules.yahtzee_counts_as_full_house && is_yahtzee(numbers, rules)) {
+                return shape_points(rules.full_house, numbers.iter().sum());
+            }
+            match detect_full_house(numbers) {
+                Some((triple, pair)) => {
+                    return shape_points(rules.full_house, 3 * triple + 2 * pair);
+                }
+                None => return 0,
+            }
         }
-        ScoreType::Sixes => {
-            sheet.sixes = Some(6 * numbers.iter().filter(|&&x| x == 6).count() as i32);
+        ScoreType::SmallStraight => {
+            if joker || is_small_straight(numbers, rules) {
+                return rules.small_straight;
+            }
+            return 0;
         }
-        ScoreType::ThreeOfAKind => {
-            let unique_counts = utils::count_unique_elements(numbers);
-            let unique_vals: Vec<i32> = unique_counts
-                .into_iter()
-                .map(|(_key, val)| val as i32)
-                .collect();
-            let res = if *(unique_vals.iter().max().unwrap_or(&0)) >= 3 {
-                numbers.iter().sum()
-            } else {
-                0
-            };
-            sheet.three_of_a_kind = Some(res);
+        ScoreType::LargeStraight => {
+            if joker || is_large_straight(numbers, rules) {
+                return rules.large_straight;
+            }
+            return 0;
         }
-        ScoreType::FourOfAKind => {
-            let unique_counts = utils::count_unique_elements(numbers);
-            let unique_vals: Vec<i32> = unique_counts
-                .into_iter()
-                .map(|(_key, val)| val as i32)
-                .collect();
-            let res = if *(unique_vals.iter().max().unwrap_or(&0)) >= 4 {
-                numbers.iter().sum()
-            } else {
-                0
-            };
-            sheet.four_of_a_kind = Some(res);
+        ScoreType::FullStraight => {
+            // all dice in one run, 1 to 6 with six dice
+            if longest_run(numbers) >= rules.n_dice {
+                return numbers.iter().sum();
+            }
+            return 0;
         }
-        ScoreType::FullHouse => {
+        ScoreType::Castle => {
             let unique_counts = utils::count_unique_elements(numbers);
-            let mut unique_vals: Vec<i32> = unique_counts
+            let mut triples: Vec<i32> = unique_counts
                 .into_iter()
-                .map(|(_key, val)| val as i32)
+                .filter(|&(_num, count)| count >= 3)
+                .map(|(num, _count)| num)
                 .collect();
-            unique_vals.sort();
-            let res = if utils::vecs_elementwise_equal(&unique_vals, &Vec::from([2, 3])) {
-                25
-            } else {
-                0
-            };
-            sheet.full_house = Some(res);
-        }
-        ScoreType::SmallStraight => {
-            let res = if detect_small_straight(numbers) {
-                30
-            } else {
-                0
-            };
-            sheet.small_straight = Some(res);
-        }
-        ScoreType::LargeStraight => {
-            let res = if detect_large_straight(numbers) {
-                40
-            } else {
-                0
-            };
-            sheet.large_straight = Some(res);
+            if triples.len() < 2 {
+                return 0;
+            }
+            triples.sort();
+            return 3 * triples.iter().rev().take(2).sum::<i32>();
         }
+        ScoreType::Tower => match detect_kind_and_pair(numbers, 4) {
+            Some((four, pair)) => return 4 * four + 2 * pair,
+            None => return 0,
+        },
         ScoreType::Yahtzee => {
-            let unique_counts = utils::count_unique_elements(numbers);
-            let res = if unique_counts.len() == 1 { 50 } else { 0 };
-            sheet.yahtzee = Some(res);
+            if is_yahtzee(numbers, rules) {
+                return rules.yahtzee;
+            }
+            return 0;
         }
-        ScoreType::Chance => sheet.chance = Some(numbers.iter().sum()),
+        ScoreType::Chance => return numbers.iter().sum(),
     }
 }
 
-fn play_turn(dice: &mut Dice, sheet: &mut ScoreSheet) {
-    // Handle one turn (up to three dice throws) for one player.
-    let mut kept_numbers: Vec<i32> = Vec::with_capacity(5);
+/// Write the numbers to `score_t` and count a Yahtzee bonus if one was earned.
+/// Does not check whether the box may be written, see `check_score_type`.
+pub fn update_score_sheet(
+    sheet: &mut ScoreSheet,
+    score_t: ScoreType,
+    numbers: &[i32],
+    rules: &Ruleset,
+) {
+    if rules.yahtzee_bonus > 0
+        && is_yahtzee(numbers, rules)
+        && sheet.get_score(ScoreType::Yahtzee) == Some(rules.yahtzee)
+    {
+        sheet.yahtzee_bonus_count += 1;
+    }
+    let points = potential_score(sheet, score_t, numbers, rules);
+    sheet.set_score(score_t, points);
+}
 
-    // round 1 and 2: throw and pick
-    for round in 0..2 {
-        println!("throw {:?}", round);
-        // numbers to choose from = numbers kept from last round + random new ones
-        let mut numbers: Vec<i32> = vec![0; 5];
-        let n_kept_numbers = kept_numbers.len();
-        utils::set_slice_from_vec(&mut numbers, kept_numbers, 0);
-        utils::set_slice_from_vec(
-            &mut numbers,
-            dice.gen_numbers(5 - n_kept_numbers),
-            n_kept_numbers,
+/// How much writing the numbers to `score_t` adds to the sheet's total, including the
+/// Yahtzee bonus and the upper bonus. A box that scores 0 may still gain points.
+pub fn score_gain(sheet: &ScoreSheet, score_t: ScoreType, numbers: &[i32], rules: &Ruleset) -> i32 {
+    return sheet.with_score(score_t, numbers, rules).sum(rules) - sheet.sum(rules);
+}
+
+fn play_turn(game: &mut GameState, player: &mut dyn Player) {
+    // Handle one turn (up to `rules.n_throws` dice throws) for the current player.
+    let n_dice = game.rules().n_dice;
+
+    // throw and pick until all dice are kept or no throws are left. A resumed game can
+    // continue after a throw in the middle of the turn
+    if game.numbers().is_empty() {
+        println!(
+            "throw {:?} ({} left)",
+            game.n_throws(),
+            game.throws_left() - 1
         );
-        loop {
-            kept_numbers = decide_keep_dice(&numbers);
-            let validation_result = validate_kept_numbers(&kept_numbers, &numbers);
-            match validation_result {
+        game.roll().expect("there are throws left");
+    }
+    loop {
+        while game.can_announce() {
+            match player.decide_announcement(game) {
+                Some((column, score_t)) => match game.announce(column, score_t) {
+                    Ok(_) => println!("announced {score_t:?} in column {}", column + 1),
+                    Err(e) => println!("{}", warning(&format!("invalid announcement: {e}"))),
+                },
+                None if game.must_announce() => {
+                    println!("only announced columns are left, you have to announce a box")
+                }
+                None => break,
+            }
+        }
+        if game.throws_left() == 0 || game.kept_numbers().len() == n_dice {
+            break;
+        }
+        let kept_numbers = loop {
+            match player
+                .decide_held_dice(game)
+                .and_then(|held| game.hold(held))
+            {
                 Ok(_) => {
+                    let kept_numbers = game.kept_numbers();
                     println!("valid choice, keeping {kept_numbers:?}");
-                    break;
+                    break kept_numbers;
                 }
-                Err(e) => println!("invalid choice. reason: {e}"),
+                Err(e) => println!("{}", warning(&format!("invalid choice. reason: {e}"))),
             }
-        }
-        if kept_numbers.len() == 5 {
+        };
+        if kept_numbers.len() == n_dice {
             break;
         }
+        println!(
+            "throw {:?} ({} left)",
+            game.n_throws(),
+            game.throws_left() - 1
+        );
+        game.roll().expect("there are throws left");
     }
 
-    //round 3: pick all remaining numbers randomly
-    let n_kept_numbers = kept_numbers.len();
-    let mut numbers: Vec<i32> = vec![0; 5];
-    utils::set_slice_from_vec(&mut numbers, kept_numbers, 0);
-    utils::set_slice_from_vec(
-        &mut numbers,
-        dice.gen_numbers(5 - n_kept_numbers),
-        n_kept_numbers,
-    );
-
-    let mut chosen_score_t: ScoreType;
     loop {
-        let choice_res = decide_scoresheet_update(&numbers);
-        match choice_res {
-            Ok(x) => chosen_score_t = x,
+        let (column, chosen_score_t) = match player.decide_scoresheet_update(game) {
+            Ok(x) => x,
             Err(e) => {
-                println!("invalid input {e}. try again.");
+                println!("{}", warning(&format!("invalid input {e}. try again.")));
                 continue;
             }
+        };
+
+        match game.choose_category(column, chosen_score_t) {
+            Ok(_) => {
+                if game.rules().columns.len() > 1 {
+                    println!("writing {chosen_score_t:?} in column {}", column + 1);
+                } else {
+                    println!("writing {chosen_score_t:?}");
+                }
+                break;
+            }
+            Err(e) => println!("{}", warning(&format!("invalid choice: {e}"))),
         }
+    }
+}
 
-        if !sheet.is_already_written(chosen_score_t) {
-            println!("writing {chosen_score_t:?}");
+fn play_turn_headless(game: &mut GameState, player: &mut dyn Player) -> Result<(), String> {
+    // like play_turn without any output, an invalid decision is an error
+    game.roll()?;
+    if game.can_announce() {
+        if let Some((column, score_t)) = player.decide_announcement(game) {
+            game.announce(column, score_t)?;
+        }
+    }
+    while game.throws_left() > 0 {
+        let held = player.decide_held_dice(game)?;
+        game.hold(held)?;
+        if game.kept_numbers().len() == game.rules().n_dice {
             break;
-        } else {
-            println!("invalid choice: {chosen_score_t:?} already written")
         }
+        game.roll()?;
     }
+    let (column, score_t) = player.decide_scoresheet_update(game)?;
+    game.choose_category(column, score_t)?;
+    return Ok(());
+}
 
-    update_score_sheet(sheet, chosen_score_t, &numbers);
+/// Play a full game without any output, e.g. for simulations. Fails on the first invalid
+/// decision of a player.
+pub fn play_game_headless(
+    seats: Vec<Seat>,
+    dice: Dice,
+    rules: Ruleset,
+) -> Result<GameState, String> {
+    let (names, mut players): (Vec<String>, Vec<Box<dyn Player>>) = seats.into_iter().unzip();
+    let mut game = GameState::new(names, dice, rules);
+    while !game.is_finished() {
+        let player = players[game.current_player()].as_mut();
+        play_turn_headless(&mut game, player)?;
+        game.advance_player()?;
+    }
+    return Ok(game);
 }
 
 fn get_player_names() -> Vec<String> {
     // get player names from user input. Chooses default name if input is empty.
-    println!("Enter player names separated by whitespace");
+    println!("Enter player names separated by whitespace. Add computer opponents with bot:easy, bot:greedy, bot:optimal or bot:optimal:<noise>");
 
     let mut input = String::new();
     io::stdin()
         .read_line(&mut input)
         .expect("Failed to read line");
-    let mut player_names: Vec<String> = input.trim().split_whitespace().map(String::from).collect();
+    let mut player_names: Vec<String> = input.split_whitespace().map(String::from).collect();
     if player_names.is_empty() {
         player_names = vec![String::from("default_name")];
     }
     return player_names;
 }
 
-pub fn play_game() {
-    {
-        let mut dice = Dice::new();
+/// A player's name and who makes the decisions for them.
+pub type Seat = (String, Box<dyn Player>);
+
+/// Seats for the players: humans on the console for plain names and bots for names like
+/// bot:greedy. Optimal bots share one solver.
+pub fn create_seats(names: Vec<String>, rules: &Ruleset, seed: u64) -> Result<Vec<Seat>, String> {
+    let mut solver = None;
+    let mut seats = Vec::with_capacity(names.len());
+    for (seat, name) in names.into_iter().enumerate() {
+        let difficulty = match Difficulty::from_name(&name) {
+            None => {
+                seats.push((name, Box::new(HumanPlayer::new()) as Box<dyn Player>));
+                continue;
+            }
+            Some(difficulty) => difficulty?,
+        };
+        if matches!(difficulty, Difficulty::Optimal { .. }) && solver.is_none() {
+            solver = Some(Arc::new(Mutex::new(solver::Solver::new(rules.clone())?)));
+        }
+        // every bot gets its own random numbers, reproducible with the dice seed
+        let bot = create_bot(difficulty, seed.wrapping_add(seat as u64), solver.clone())?;
+        seats.push((name, bot));
+    }
+    return Ok(seats);
+}
+
+fn get_ruleset() -> Ruleset {
+    // ask for the rules to play with. Chooses Kniffel if input is empty.
+    let names = Ruleset::names().join(", ");
+    loop {
+        println!("Enter the rules to play with ({names}). Press enter for kniffel");
+
+        let mut input = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read line");
+        if input.trim().is_empty() {
+            return Ruleset::kniffel();
+        }
+        match Ruleset::from_name(&input) {
+            Ok(rules) => return rules,
+            Err(e) => println!("{e}. try again."),
+        }
+    }
+}
+
+/// How a console game is set up. Everything that is not given is asked for.
+#[derive(Debug, Clone, Default)]
+pub struct PlayOptions {
+    pub seed: Option<u64>,
+    pub rules: Option<Ruleset>,
+    /// names of the players in seating order, bots are named like bot:greedy
+    pub players: Option<Vec<String>>,
+    /// the record of the game is written to this file once it is finished
+    pub record: Option<PathBuf>,
+}
+
+impl PlayOptions {
+    /// Whether a new game is set up explicitly, then an unfinished game is not offered.
+    pub fn sets_up_game(&self) -> bool {
+        return self.seed.is_some() || self.rules.is_some() || self.players.is_some();
+    }
+}
+
+/// The file the console game is saved to after every scored box, in the home directory
+/// if there is one.
+pub fn autosave_path() -> PathBuf {
+    let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"));
+    let dir = home.map(PathBuf::from).unwrap_or_default();
+    return dir.join(".kniffel-autosave.json");
+}
+
+pub(crate) fn unfinished_game(path: &Path) -> Option<GameState> {
+    // a game left behind by a crash or a closed terminal
+    return GameState::load(path)
+        .ok()
+        .filter(|game| !game.is_finished());
+}
+
+pub(crate) fn ask_to_continue(game: &GameState) -> bool {
+    println!(
+        "Found an unfinished game of {} in turn {} ({} rules). Continue it? (Y/n)",
+        game.players().join(", "),
+        game.turn(),
+        game.rules().name
+    );
+    let mut input = String::new();
+    io::stdin()
+        .read_line(&mut input)
+        .expect("Failed to read line");
+    return !input.trim().eq_ignore_ascii_case("n");
+}
+
+/// Play on the console. The dice seed is chosen randomly if not given. An unfinished
+/// game from the autosave can be continued instead.
+pub fn play_game(options: PlayOptions) {
+    let record = options.record.as_deref();
+    let autosave = autosave_path();
+    if let Some(game) = unfinished_game(&autosave).filter(|_| !options.sets_up_game()) {
+        if ask_to_continue(&game) {
+            if let Err(e) = continue_saved_game(game, record) {
+                println!("{e}");
+            }
+            return;
+        }
+    }
+    let seed = options.seed.unwrap_or_else(Dice::random_seed);
+    println!("Dice seed for this game is {seed}");
+    let rules = options.rules.unwrap_or_else(get_ruleset);
+    println!("Playing with {} rules", rules.name);
+    let mut names = options.players;
+    let seats = loop {
+        match create_seats(names.take().unwrap_or_else(get_player_names), &rules, seed) {
+            Ok(seats) => break seats,
+            Err(e) => println!("{e}. try again."),
+        }
+    };
+    let (names, players): (Vec<String>, Vec<Box<dyn Player>>) = seats.into_iter().unzip();
+    let game = GameState::new(names, Dice::seeded(seed), rules);
+    let game = continue_game(game, players, Some(&autosave));
+    finish_game(&game, record);
+}
+
+/// Continue a game saved with the save command on the console. Bots are recreated from
+/// their names.
+pub fn resume_game(path: &Path, record: Option<&Path>) -> Result<(), String> {
+    let game = GameState::load(path)?;
+    println!(
+        "Resuming the game from {} with {} rules",
+        path.display(),
+        game.rules().name
+    );
+    return continue_saved_game(game, record);
+}
 
-        let players = get_player_names();
+fn continue_saved_game(game: GameState, record: Option<&Path>) -> Result<(), String> {
+    let seed = game.dice_seed().unwrap_or_else(Dice::random_seed);
+    let seats = create_seats(game.players().to_vec(), game.rules(), seed)?;
+    let players = seats.into_iter().map(|(_, player)| player).collect();
+    let game = continue_game(game, players, Some(&autosave_path()));
+    finish_game(&game, record);
+    return Ok(());
+}
 
-        let mut score_sheets: HashMap<String, ScoreSheet> = HashMap::new();
-        for player in players {
-            score_sheets.insert(String::from(player), ScoreSheet::new());
+fn finish_game(game: &GameState, record: Option<&Path>) {
+    if let Some(path) = record {
+        match record::GameRecord::from_game(game).save(path) {
+            Ok(_) => println!("game record written to {}", path.display()),
+            Err(e) => println!("{e}"),
         }
+    }
+    if ask_for_analysis() {
+        analysis::print_analysis(game);
+    }
+}
 
-        for turn in 1..14 {
+fn ask_for_analysis() -> bool {
+    println!("Analyze the decisions of this game? This takes a while. (y/N)");
+    let mut input = String::new();
+    io::stdin()
+        .read_line(&mut input)
+        .expect("Failed to read line");
+    return input.trim().eq_ignore_ascii_case("y");
+}
+
+/// Play a full game on the console with any mix of players, seated in the given order.
+/// Returns the final sums in seating order.
+pub fn play_game_with_players(
+    seats: Vec<(String, Box<dyn Player>)>,
+    dice: Dice,
+    rules: Ruleset,
+) -> Vec<(String, i32)> {
+    return run_game(seats, dice, rules).results();
+}
+
+fn run_game(seats: Vec<(String, Box<dyn Player>)>, dice: Dice, rules: Ruleset) -> GameState {
+    let (names, players): (Vec<String>, Vec<Box<dyn Player>>) = seats.into_iter().unzip();
+    return continue_game(GameState::new(names, dice, rules), players, None);
+}
+
+fn continue_game(
+    mut game: GameState,
+    mut players: Vec<Box<dyn Player>>,
+    autosave: Option<&Path>,
+) -> GameState {
+    // with an autosave file the game is saved after every scored box and the file is
+    // removed once the game is finished
+    while !game.is_finished() {
+        let turn = game.turn();
+        if game.current_player() == 0 && game.numbers().is_empty() {
             println!("Start of turn {turn}. Scores so far are");
-            print_all_score_sheets(&mut score_sheets);
+            print_all_score_sheets(&game);
             println!();
-            for (player, sheet_ref) in &mut score_sheets {
-                println!("turn {turn} for player {player}.");
-                play_turn(&mut dice, sheet_ref);
-                println!();
-            }
         }
-        println!("final result:");
-        for (player, sheet) in score_sheets {
-            let sum = sheet.sum();
-            println!("{player}: {sum}")
+        let player_name = game.current_player_name().to_string();
+        println!(
+            "{}",
+            highlight(&format!("turn {turn} for player {player_name}."))
+        );
+        let player = players[game.current_player()].as_mut();
+        play_turn(&mut game, player);
+        println!();
+        game.advance_player()
+            .expect("the player has written a score this turn");
+        if let Some(path) = autosave {
+            if let Err(e) = game.save(path) {
+                println!("autosave failed: {e}");
+            }
         }
     }
+    if let Some(path) = autosave {
+        // nothing to recover any more
+        let _ = fs::remove_file(path);
+    }
+
+    println!("{}", highlight("final result:"));
+    for (player, sum) in game.results() {
+        println!("{player}: {sum}")
+    }
+    return game;
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    struct InOrderPlayer;
+
+    impl Player for InOrderPlayer {
+        fn decide_keep_dice(&mut self, _game: &GameState) -> Vec<i32> {
+            return vec![];
+        }
+
+        fn decide_scoresheet_update(
+            &mut self,
+            game: &GameState,
+        ) -> Result<(usize, ScoreType), String> {
+            match game.legal_boxes().first() {
+                Some(legal) => return Ok(*legal),
+                None => return Err(String::from("sheet is full")),
+            }
+        }
+    }
+
+    #[test]
+    fn play_game_with_bots() {
+        let seats: Vec<(String, Box<dyn Player>)> = vec![
+            (String::from("bot1"), Box::new(InOrderPlayer)),
+            (String::from("bot2"), Box::new(InOrderPlayer)),
+        ];
+        let results = play_game_with_players(seats, Dice::seeded(7), Ruleset::kniffel());
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, "bot1");
+        assert_eq!(results[1].0, "bot2");
+    }
+
+    #[test]
+    fn kniffel_block_game() {
+        let seats: Vec<(String, Box<dyn Player>)> =
+            vec![(String::from("bot"), Box::new(InOrderPlayer))];
+        let results = play_game_with_players(seats, Dice::seeded(9), Ruleset::kniffel_block());
+        assert!(results[0].1 > 0);
+    }
+
+    #[test]
+    fn setup_options() {
+        assert!(!PlayOptions::default().sets_up_game());
+        let record_only = PlayOptions {
+            record: Some(PathBuf::from("game.kn")),
+            ..PlayOptions::default()
+        };
+        assert!(!record_only.sets_up_game());
+        let seeded = PlayOptions {
+            seed: Some(1),
+            ..PlayOptions::default()
+        };
+        assert!(seeded.sets_up_game());
+    }
+
+    #[test]
+    fn autosave() {
+        let path = env::temp_dir().join(format!("kniffel-autosave-{}.json", std::process::id()));
+        let mut game = GameState::new(
+            vec![String::from("bot")],
+            Dice::seeded(4),
+            Ruleset::kniffel(),
+        );
+        game.roll().unwrap();
+        game.choose_category(0, ScoreType::Chance).unwrap();
+        game.advance_player().unwrap();
+        game.save(&path).unwrap();
+        let unfinished = unfinished_game(&path).unwrap();
+        assert_eq!(unfinished.turn(), 2);
+
+        // the finished game removes the autosave
+        let game = continue_game(unfinished, vec![Box::new(InOrderPlayer)], Some(&path));
+        assert!(game.is_finished());
+        assert!(!path.exists());
+        assert!(unfinished_game(&path).is_none());
+    }
+
+    #[test]
+    fn headless_game() {
+        let seats: Vec<Seat> = vec![
+            (String::from("bot1"), Box::new(InOrderPlayer)),
+            (String::from("bot2"), Box::new(InOrderPlayer)),
+        ];
+        let game = play_game_headless(seats, Dice::seeded(7), Ruleset::kniffel()).unwrap();
+        assert!(game.is_finished());
+        let seats: Vec<Seat> = vec![
+            (String::from("bot1"), Box::new(InOrderPlayer)),
+            (String::from("bot2"), Box::new(InOrderPlayer)),
+        ];
+        let results = play_game_with_players(seats, Dice::seeded(7), Ruleset::kniffel());
+        assert_eq!(game.results(), results);
+    }
+
+    #[test]
+    fn triple_yahtzee_game() {
+        let seats: Vec<(String, Box<dyn Player>)> =
+            vec![(String::from("bot"), Box::new(InOrderPlayer))];
+        let rolls = [2, 3, 4, 5, 6].repeat(3 * 39);
+        let results =
+            play_game_with_players(seats, Dice::scripted(rolls), Ruleset::triple_yahtzee());
+        // each column: 2 + 3 + 4 + 5 + 6 in the upper half, small and large straight and chance
+        assert_eq!(results[0].1, (1 + 2 + 3) * (20 + 30 + 40 + 20));
+    }
+
+    #[test]
+    fn scripted_game() {
+        // every turn: three throws without keeping, only the last one counts
+        let mut rolls = Vec::new();
+        for _turn in 0..13 {
+            rolls.extend([1, 1, 1, 1, 1, 6, 6, 6, 6, 6, 2, 3, 4, 5, 6]);
+        }
+        let seats: Vec<(String, Box<dyn Player>)> =
+            vec![(String::from("bot"), Box::new(InOrderPlayer))];
+        let results = play_game_with_players(seats, Dice::scripted(rolls), Ruleset::kniffel());
+        // 2 + 3 + 4 + 5 + 6 in the upper half, small and large straight and chance
+        assert_eq!(results[0].1, 20 + 30 + 40 + 20);
+    }
+
     mod update_scores {
-        use super::{update_score_sheet, ScoreSheet};
+        use super::{
+            check_score_type, potential_score, score_gain, update_score_sheet, Ruleset, ScoreSheet,
+            ScoreType,
+        };
+
+        fn rules() -> Ruleset {
+            return Ruleset::hasbro_yahtzee();
+        }
 
         #[test]
         fn ones() {
             let mut sheet = ScoreSheet::new();
             let dice = vec![1, 1, 3, 1, 2];
-            update_score_sheet(&mut sheet, super::ScoreType::Ones, &dice);
-            assert_eq!(sheet.ones.unwrap(), 3);
+            update_score_sheet(&mut sheet, ScoreType::Ones, &dice, &rules());
+            assert_eq!(sheet.get_score(ScoreType::Ones).unwrap(), 3);
         }
         #[test]
         fn twos() {
             let mut sheet = ScoreSheet::new();
             let dice = vec![1, 2, 3, 1, 2];
-            update_score_sheet(&mut sheet, super::ScoreType::Twos, &dice);
-            assert_eq!(sheet.twos.unwrap(), 4);
+            update_score_sheet(&mut sheet, ScoreType::Twos, &dice, &rules());
+            assert_eq!(sheet.get_score(ScoreType::Twos).unwrap(), 4);
         }
         #[test]
         fn threes() {
             let mut sheet = ScoreSheet::new();
             let dice = vec![3, 1, 3, 1, 2];
-            update_score_sheet(&mut sheet, super::ScoreType::Threes, &dice);
-            assert_eq!(sheet.threes.unwrap(), 6);
+            update_score_sheet(&mut sheet, ScoreType::Threes, &dice, &rules());
+            assert_eq!(sheet.get_score(ScoreType::Threes).unwrap(), 6);
         }
         #[test]
         fn fours() {
             let mut sheet = ScoreSheet::new();
             let dice = vec![1, 4, 4, 1, 4];
-            update_score_sheet(&mut sheet, super::ScoreType::Fours, &dice);
-            assert_eq!(sheet.fours.unwrap(), 12);
+            update_score_sheet(&mut sheet, ScoreType::Fours, &dice, &rules());
+            assert_eq!(sheet.get_score(ScoreType::Fours).unwrap(), 12);
         }
         #[test]
         fn fives() {
             let mut sheet = ScoreSheet::new();
             let dice = vec![5, 5, 3, 1, 5];
-            update_score_sheet(&mut sheet, super::ScoreType::Fives, &dice);
-            assert_eq!(sheet.fives.unwrap(), 15);
+            update_score_sheet(&mut sheet, ScoreType::Fives, &dice, &rules());
+            assert_eq!(sheet.get_score(ScoreType::Fives).unwrap(), 15);
         }
         #[test]
         fn sixes() {
             let mut sheet = ScoreSheet::new();
             let dice = vec![1, 1, 6, 1, 6];
-            update_score_sheet(&mut sheet, super::ScoreType::Sixes, &dice);
-            assert_eq!(sheet.sixes.unwrap(), 12);
+            update_score_sheet(&mut sheet, ScoreType::Sixes, &dice, &rules());
+            assert_eq!(sheet.get_score(ScoreType::Sixes).unwrap(), 12);
         }
         #[test]
         fn three_of_a_kind() {
             let mut sheet = ScoreSheet::new();
             let dice_fulfill = vec![1, 1, 3, 1, 2];
             let dice_scratch = vec![1, 3, 3, 1, 2];
-            update_score_sheet(&mut sheet, super::ScoreType::ThreeOfAKind, &dice_fulfill);
-            assert_eq!(sheet.three_of_a_kind.unwrap(), 8);
-            update_score_sheet(&mut sheet, super::ScoreType::ThreeOfAKind, &dice_scratch);
-            assert_eq!(sheet.three_of_a_kind.unwrap(), 0);
+            update_score_sheet(&mut sheet, ScoreType::ThreeOfAKind, &dice_fulfill, &rules());
+            assert_eq!(sheet.get_score(ScoreType::ThreeOfAKind).unwrap(), 8);
+            update_score_sheet(&mut sheet, ScoreType::ThreeOfAKind, &dice_scratch, &rules());
+            assert_eq!(sheet.get_score(ScoreType::ThreeOfAKind).unwrap(), 0);
         }
         #[test]
         fn four_of_a_kind() {
             let mut sheet = ScoreSheet::new();
             let dice_fulfill = vec![1, 1, 3, 1, 1];
             let dice_scratch = vec![1, 3, 3, 1, 1];
-            update_score_sheet(&mut sheet, super::ScoreType::FourOfAKind, &dice_fulfill);
-            assert_eq!(sheet.four_of_a_kind.unwrap(), 7);
-            update_score_sheet(&mut sheet, super::ScoreType::FourOfAKind, &dice_scratch);
-            assert_eq!(sheet.four_of_a_kind.unwrap(), 0);
+            update_score_sheet(&mut sheet, ScoreType::FourOfAKind, &dice_fulfill, &rules());
+            assert_eq!(sheet.get_score(ScoreType::FourOfAKind).unwrap(), 7);
+            update_score_sheet(&mut sheet, ScoreType::FourOfAKind, &dice_scratch, &rules());
+            assert_eq!(sheet.get_score(ScoreType::FourOfAKind).unwrap(), 0);
         }
         #[test]
         fn full_house() {
             let mut sheet = ScoreSheet::new();
             let dice_fulfill = vec![1, 1, 3, 3, 1];
             let dice_scratch = vec![1, 3, 3, 1, 2];
-            update_score_sheet(&mut sheet, super::ScoreType::FullHouse, &dice_fulfill);
-            assert_eq!(sheet.full_house.unwrap(), 25);
-            update_score_sheet(&mut sheet, super::ScoreType::FullHouse, &dice_scratch);
-            assert_eq!(sheet.full_house.unwrap(), 0);
+            update_score_sheet(&mut sheet, ScoreType::FullHouse, &dice_fulfill, &rules());
+            assert_eq!(sheet.get_score(ScoreType::FullHouse).unwrap(), 25);
+            update_score_sheet(&mut sheet, ScoreType::FullHouse, &dice_scratch, &rules());
+            assert_eq!(sheet.get_score(ScoreType::FullHouse).unwrap(), 0);
         }
         #[test]
         fn small_straight() {
-            let mut legal_dice: Vec<Vec<i32>> = Vec::new();
-            legal_dice.push(vec![1, 2, 3, 4, 5]);
-            legal_dice.push(vec![1, 3, 2, 4, 5]);
-            legal_dice.push(vec![1, 2, 3, 4, 1]);
-            legal_dice.push(vec![3, 4, 5, 6, 1]);
-            legal_dice.push(vec![3, 3, 4, 5, 6]);
+            let legal_dice: Vec<Vec<i32>> = vec![
+                vec![1, 2, 3, 4, 5],
+                vec![1, 3, 2, 4, 5],
+                vec![1, 2, 3, 4, 1],
+                vec![3, 4, 5, 6, 1],
+                vec![3, 3, 4, 5, 6],
+            ];
             for throw in legal_dice {
                 let mut sheet = ScoreSheet::new();
-                update_score_sheet(&mut sheet, super::ScoreType::SmallStraight, &throw);
-                assert_eq!(sheet.small_straight.unwrap(), 30);
+                update_score_sheet(&mut sheet, ScoreType::SmallStraight, &throw, &rules());
+                assert_eq!(sheet.get_score(ScoreType::SmallStraight).unwrap(), 30);
             }
 
-            let mut illegal_dice: Vec<Vec<i32>> = Vec::new();
-            illegal_dice.push(vec![1, 1, 1, 2, 3]);
-            illegal_dice.push(vec![1, 2, 3, 5, 6]);
+            let illegal_dice: Vec<Vec<i32>> = vec![vec![1, 1, 1, 2, 3], vec![1, 2, 3, 5, 6]];
             for throw in illegal_dice {
                 let mut sheet = ScoreSheet::new();
-                update_score_sheet(&mut sheet, super::ScoreType::SmallStraight, &throw);
-                assert_eq!(sheet.small_straight.unwrap(), 0);
+                update_score_sheet(&mut sheet, ScoreType::SmallStraight, &throw, &rules());
+                assert_eq!(sheet.get_score(ScoreType::SmallStraight).unwrap(), 0);
             }
         }
         #[test]
         fn large_straight() {
-            let mut legal_dice: Vec<Vec<i32>> = Vec::new();
-            legal_dice.push(vec![1, 2, 3, 4, 5]);
-            legal_dice.push(vec![2, 3, 4, 5, 6]);
-            legal_dice.push(vec![6, 5, 4, 2, 3]);
+            let legal_dice: Vec<Vec<i32>> = vec![
+                vec![1, 2, 3, 4, 5],
+                vec![2, 3, 4, 5, 6],
+                vec![6, 5, 4, 2, 3],
+            ];
             for throw in legal_dice {
                 let mut sheet = ScoreSheet::new();
-                update_score_sheet(&mut sheet, super::ScoreType::LargeStraight, &throw);
-                assert_eq!(sheet.large_straight.unwrap(), 40);
+                update_score_sheet(&mut sheet, ScoreType::LargeStraight, &throw, &rules());
+                assert_eq!(sheet.get_score(ScoreType::LargeStraight).unwrap(), 40);
             }
 
-            let mut illegal_dice: Vec<Vec<i32>> = Vec::new();
-            illegal_dice.push(vec![1, 1, 1, 2, 3]);
-            illegal_dice.push(vec![1, 2, 3, 5, 6]);
-            illegal_dice.push(vec![1, 2, 3, 4, 6]);
+            let illegal_dice: Vec<Vec<i32>> = vec![
+                vec![1, 1, 1, 2, 3],
+                vec![1, 2, 3, 5, 6],
+                vec![1, 2, 3, 4, 6],
+            ];
             for throw in illegal_dice {
                 let mut sheet = ScoreSheet::new();
-                update_score_sheet(&mut sheet, super::ScoreType::LargeStraight, &throw);
-                assert_eq!(sheet.large_straight.unwrap(), 0);
+                update_score_sheet(&mut sheet, ScoreType::LargeStraight, &throw, &rules());
+                assert_eq!(sheet.get_score(ScoreType::LargeStraight).unwrap(), 0);
             }
         }
         #[test]
@@ -581,17 +1275,325 @@ mod tests {
             let legal_dice = vec![2, 2, 2, 2, 2];
             let illegal_dice = vec![2, 2, 2, 3, 2];
             let mut sheet = ScoreSheet::new();
-            update_score_sheet(&mut sheet, super::ScoreType::Yahtzee, &legal_dice);
-            assert_eq!(sheet.yahtzee.unwrap(), 50);
-            update_score_sheet(&mut sheet, super::ScoreType::Yahtzee, &illegal_dice);
-            assert_eq!(sheet.yahtzee.unwrap(), 0);
+            update_score_sheet(&mut sheet, ScoreType::Yahtzee, &legal_dice, &rules());
+            assert_eq!(sheet.get_score(ScoreType::Yahtzee).unwrap(), 50);
+            update_score_sheet(&mut sheet, ScoreType::Yahtzee, &illegal_dice, &rules());
+            assert_eq!(sheet.get_score(ScoreType::Yahtzee).unwrap(), 0);
+        }
+        #[test]
+        fn yahtzee_bonus() {
+            let mut sheet = ScoreSheet::new();
+            update_score_sheet(&mut sheet, ScoreType::Yahtzee, &[3, 3, 3, 3, 3], &rules());
+            assert_eq!(sheet.yahtzee_bonus_count(), 0);
+            update_score_sheet(&mut sheet, ScoreType::Threes, &[3, 3, 3, 3, 3], &rules());
+            assert_eq!(sheet.yahtzee_bonus_count(), 1);
+            update_score_sheet(&mut sheet, ScoreType::FullHouse, &[4, 4, 4, 4, 4], &rules());
+            assert_eq!(sheet.yahtzee_bonus_count(), 2);
+            assert_eq!(sheet.sum(&rules()), 50 + 15 + 25 + 200);
+        }
+        #[test]
+        fn joker_gain() {
+            let mut sheet = ScoreSheet::new();
+            update_score_sheet(&mut sheet, ScoreType::Yahtzee, &[3, 3, 3, 3, 3], &rules());
+            // a joker written to another upper box scores 0 there but earns the bonus
+            assert_eq!(
+                potential_score(&sheet, ScoreType::Ones, &[4, 4, 4, 4, 4], &rules()),
+                0
+            );
+            assert_eq!(
+                score_gain(&sheet, ScoreType::Ones, &[4, 4, 4, 4, 4], &rules()),
+                100
+            );
+            assert_eq!(
+                score_gain(&sheet, ScoreType::Ones, &[2, 3, 4, 4, 4], &rules()),
+                0
+            );
+        }
+        #[test]
+        fn no_yahtzee_bonus_after_scratch() {
+            let mut sheet = ScoreSheet::new();
+            update_score_sheet(&mut sheet, ScoreType::Yahtzee, &[1, 2, 3, 3, 3], &rules());
+            update_score_sheet(&mut sheet, ScoreType::Threes, &[3, 3, 3, 3, 3], &rules());
+            assert_eq!(sheet.yahtzee_bonus_count(), 0);
+            assert_eq!(sheet.sum(&rules()), 15);
+        }
+        #[test]
+        fn joker_scores() {
+            let mut sheet = ScoreSheet::new();
+            let dice = vec![2, 2, 2, 2, 2];
+            // before the Yahtzee box is written, the usual shape criteria apply
+            assert_eq!(
+                potential_score(&sheet, ScoreType::FullHouse, &dice, &rules()),
+                0
+            );
+            assert_eq!(
+                potential_score(&sheet, ScoreType::SmallStraight, &dice, &rules()),
+                0
+            );
+            update_score_sheet(&mut sheet, ScoreType::Yahtzee, &[1, 2, 3, 4, 6], &rules());
+            assert_eq!(
+                potential_score(&sheet, ScoreType::FullHouse, &dice, &rules()),
+                25
+            );
+            assert_eq!(
+                potential_score(&sheet, ScoreType::SmallStraight, &dice, &rules()),
+                30
+            );
+            assert_eq!(
+                potential_score(&sheet, ScoreType::LargeStraight, &dice, &rules()),
+                40
+            );
+            assert_eq!(
+                potential_score(&sheet, ScoreType::FourOfAKind, &dice, &rules()),
+                10
+            );
+            assert_eq!(
+                potential_score(&sheet, ScoreType::Chance, &dice, &rules()),
+                10
+            );
+        }
+        #[test]
+        fn joker_placement() {
+            let mut sheet = ScoreSheet::new();
+            let dice = vec![5, 5, 5, 5, 5];
+            assert!(check_score_type(&sheet, ScoreType::Chance, &dice, &rules()).is_ok());
+
+            update_score_sheet(&mut sheet, ScoreType::Yahtzee, &dice, &rules());
+            assert!(check_score_type(&sheet, ScoreType::Yahtzee, &dice, &rules()).is_err());
+            assert!(check_score_type(&sheet, ScoreType::Chance, &dice, &rules()).is_err());
+            assert!(check_score_type(&sheet, ScoreType::Ones, &dice, &rules()).is_err());
+            assert!(check_score_type(&sheet, ScoreType::Fives, &dice, &rules()).is_ok());
+
+            update_score_sheet(&mut sheet, ScoreType::Fives, &dice, &rules());
+            assert!(check_score_type(&sheet, ScoreType::Ones, &dice, &rules()).is_err());
+            assert!(check_score_type(&sheet, ScoreType::FullHouse, &dice, &rules()).is_ok());
+            assert!(check_score_type(&sheet, ScoreType::Chance, &dice, &rules()).is_ok());
+
+            for lower in rules().categories {
+                if !lower.is_upper() && !sheet.is_already_written(lower) {
+                    update_score_sheet(&mut sheet, lower, &[1, 2, 3, 4, 6], &rules());
+                }
+            }
+            assert!(check_score_type(&sheet, ScoreType::Ones, &dice, &rules()).is_ok());
         }
         #[test]
         fn chance() {
             let legal_dice = vec![1, 2, 3, 4, 5];
             let mut sheet = ScoreSheet::new();
-            update_score_sheet(&mut sheet, super::ScoreType::Chance, &legal_dice);
-            assert_eq!(sheet.chance.unwrap(), 15);
+            update_score_sheet(&mut sheet, ScoreType::Chance, &legal_dice, &rules());
+            assert_eq!(sheet.get_score(ScoreType::Chance).unwrap(), 15);
+        }
+    }
+    mod rulesets {
+        use super::{
+            check_score_type, potential_score, update_score_sheet, Ruleset, ScoreSheet, ScoreType,
+        };
+
+        #[test]
+        fn kniffel() {
+            let rules = Ruleset::kniffel();
+            let mut sheet = ScoreSheet::new();
+            let kniffel = vec![4, 4, 4, 4, 4];
+            assert_eq!(
+                potential_score(&sheet, ScoreType::FullHouse, &kniffel, &rules),
+                25
+            );
+            update_score_sheet(&mut sheet, ScoreType::Yahtzee, &kniffel, &rules);
+            update_score_sheet(&mut sheet, ScoreType::Fours, &kniffel, &rules);
+            assert_eq!(sheet.yahtzee_bonus_count(), 0);
+            // no joker: the shape criteria still apply
+            assert_eq!(
+                potential_score(&sheet, ScoreType::SmallStraight, &kniffel, &rules),
+                0
+            );
+            assert_eq!(sheet.sum(&rules), 70);
+        }
+        #[test]
+        fn upper_bonus() {
+            let rules = Ruleset::kniffel();
+            let mut sheet = ScoreSheet::new();
+            for number in 1..=6 {
+                let dice = vec![number, number, number, 1, 2];
+                update_score_sheet(
+                    &mut sheet,
+                    ScoreType::upper_for(number).unwrap(),
+                    &dice,
+                    &rules,
+                );
+            }
+            assert_eq!(sheet.upper_sum(), 66);
+            assert_eq!(sheet.sum(&rules), 66 + 35);
+            assert_eq!(sheet.sum(&Ruleset::yacht()), 66);
+        }
+        #[test]
+        fn yacht() {
+            let rules = Ruleset::yacht();
+            let sheet = ScoreSheet::new();
+            let full_house = vec![5, 5, 6, 6, 6];
+            assert_eq!(
+                potential_score(&sheet, ScoreType::FullHouse, &full_house, &rules),
+                28
+            );
+            let four_kind = vec![3, 3, 6, 3, 3];
+            assert_eq!(
+                potential_score(&sheet, ScoreType::FourOfAKind, &four_kind, &rules),
+                12
+            );
+            let yacht = vec![2, 2, 2, 2, 2];
+            assert_eq!(
+                potential_score(&sheet, ScoreType::FourOfAKind, &yacht, &rules),
+                8
+            );
+            assert_eq!(
+                potential_score(&sheet, ScoreType::FullHouse, &yacht, &rules),
+                0
+            );
+
+            let little = vec![5, 4, 3, 2, 1];
+            let big = vec![2, 3, 4, 5, 6];
+            let run_of_four = vec![1, 2, 3, 4, 6];
+            assert_eq!(
+                potential_score(&sheet, ScoreType::SmallStraight, &little, &rules),
+                30
+            );
+            assert_eq!(
+                potential_score(&sheet, ScoreType::SmallStraight, &big, &rules),
+                0
+            );
+            assert_eq!(
+                potential_score(&sheet, ScoreType::LargeStraight, &big, &rules),
+                30
+            );
+            assert_eq!(
+                potential_score(&sheet, ScoreType::LargeStraight, &little, &rules),
+                0
+            );
+            assert_eq!(
+                potential_score(&sheet, ScoreType::SmallStraight, &run_of_four, &rules),
+                0
+            );
+        }
+        #[test]
+        fn yatzy() {
+            let rules = Ruleset::yatzy();
+            let sheet = ScoreSheet::new();
+            let cases = vec![
+                (ScoreType::OnePair, vec![3, 3, 5, 5, 1], 10),
+                (ScoreType::OnePair, vec![1, 2, 3, 4, 6], 0),
+                (ScoreType::TwoPairs, vec![3, 3, 5, 5, 1], 16),
+                (ScoreType::TwoPairs, vec![2, 2, 5, 5, 5], 14),
+                (ScoreType::TwoPairs, vec![4, 4, 4, 4, 1], 0),
+                (ScoreType::ThreeOfAKind, vec![4, 4, 4, 2, 1], 12),
+                (ScoreType::FourOfAKind, vec![6, 6, 6, 6, 6], 24),
+                (ScoreType::SmallStraight, vec![1, 2, 3, 4, 5], 15),
+                (ScoreType::SmallStraight, vec![2, 3, 4, 5, 6], 0),
+                (ScoreType::LargeStraight, vec![2, 3, 4, 5, 6], 20),
+                (ScoreType::FullHouse, vec![2, 2, 6, 6, 6], 22),
+                (ScoreType::FullHouse, vec![6, 6, 6, 6, 6], 0),
+                (ScoreType::Yahtzee, vec![1, 1, 1, 1, 1], 50),
+            ];
+            for (score_t, dice, points) in cases {
+                assert_eq!(potential_score(&sheet, score_t, &dice, &rules), points);
+            }
+
+            let mut sheet = ScoreSheet::new();
+            for number in 1..=6 {
+                let dice = vec![number, number, number, 6, 6];
+                update_score_sheet(
+                    &mut sheet,
+                    ScoreType::upper_for(number).unwrap(),
+                    &dice,
+                    &rules,
+                );
+            }
+            assert_eq!(sheet.upper_sum(), 3 * 21 + 12);
+            assert_eq!(sheet.sum(&rules), 3 * 21 + 12 + 50);
+        }
+
+        #[test]
+        fn maxi_yatzy() {
+            let rules = Ruleset::maxi_yatzy();
+            let sheet = ScoreSheet::new();
+            let cases = vec![
+                (ScoreType::ThreePairs, vec![1, 1, 3, 3, 6, 6], 20),
+                (ScoreType::ThreePairs, vec![1, 1, 3, 3, 3, 3], 0),
+                (ScoreType::TwoPairs, vec![1, 1, 3, 3, 6, 6], 18),
+                (ScoreType::FiveOfAKind, vec![2, 2, 2, 2, 2, 6], 10),
+                (ScoreType::FullHouse, vec![2, 2, 5, 5, 5, 6], 19),
+                (ScoreType::FullHouse, vec![2, 2, 2, 5, 5, 5], 19),
+                (ScoreType::Castle, vec![2, 2, 2, 5, 5, 5], 21),
+                (ScoreType::Castle, vec![2, 2, 5, 5, 5, 5], 0),
+                (ScoreType::Tower, vec![2, 2, 5, 5, 5, 5], 24),
+                (ScoreType::Tower, vec![2, 5, 5, 5, 5, 5], 0),
+                (ScoreType::SmallStraight, vec![1, 2, 3, 4, 5, 5], 15),
+                (ScoreType::LargeStraight, vec![6, 2, 3, 4, 5, 5], 20),
+                (ScoreType::LargeStraight, vec![1, 2, 3, 4, 5, 5], 0),
+                (ScoreType::FullStraight, vec![6, 5, 4, 3, 2, 1], 21),
+                (ScoreType::FullStraight, vec![1, 2, 3, 4, 5, 5], 0),
+                (ScoreType::Yahtzee, vec![4, 4, 4, 4, 4, 4], 100),
+                (ScoreType::Yahtzee, vec![4, 4, 4, 4, 4, 1], 0),
+            ];
+            for (score_t, dice, points) in cases {
+                assert_eq!(potential_score(&sheet, score_t, &dice, &rules), points);
+            }
+        }
+        #[test]
+        fn full_straight_with_more_dice() {
+            let rules = Ruleset {
+                categories: vec![ScoreType::FullStraight],
+                ..Ruleset::kniffel().with_dice(7, 8)
+            };
+            let sheet = ScoreSheet::new();
+            let cases = vec![
+                (vec![1, 2, 3, 4, 5, 6, 7], 28),
+                (vec![8, 7, 6, 5, 4, 3, 2], 35),
+                (vec![1, 2, 3, 4, 5, 6, 6], 0),
+                (vec![1, 2, 3, 4, 5, 6, 8], 0),
+            ];
+            for (dice, points) in cases {
+                assert_eq!(
+                    potential_score(&sheet, ScoreType::FullStraight, &dice, &rules),
+                    points
+                );
+            }
+        }
+        #[test]
+        fn runs_with_six_dice() {
+            let rules = Ruleset {
+                n_dice: 6,
+                ..Ruleset::kniffel()
+            };
+            let sheet = ScoreSheet::new();
+            let dice = vec![1, 2, 3, 4, 5, 6];
+            assert_eq!(
+                potential_score(&sheet, ScoreType::SmallStraight, &dice, &rules),
+                30
+            );
+            assert_eq!(
+                potential_score(&sheet, ScoreType::LargeStraight, &dice, &rules),
+                40
+            );
+        }
+
+        #[test]
+        fn eight_faces() {
+            let rules = Ruleset::hasbro_yahtzee().with_dice(5, 8);
+            let mut sheet = ScoreSheet::new();
+            let dice = vec![4, 5, 6, 7, 8];
+            assert_eq!(
+                potential_score(&sheet, ScoreType::LargeStraight, &dice, &rules),
+                40
+            );
+
+            // a joker of sevens has no upper box and goes to any open lower box
+            let sevens = vec![7, 7, 7, 7, 7];
+            update_score_sheet(&mut sheet, ScoreType::Yahtzee, &sevens, &rules);
+            assert!(check_score_type(&sheet, ScoreType::Chance, &sevens, &rules).is_ok());
+            assert!(check_score_type(&sheet, ScoreType::Sixes, &sevens, &rules).is_err());
+            assert_eq!(
+                potential_score(&sheet, ScoreType::FullHouse, &sevens, &rules),
+                25
+            );
         }
     }
 }
@@ -0,0 +1,418 @@
+//! A compact text notation for games, in the spirit of chess PGN:
+//!
+//! ```text
+//! [Rules "kniffel"]
+//! [Seed "3"]
+//! [P1 "alice"]
+//! [P2 "bob"]
+//!
+//! P1: 2-3-3-5-6 k33 / 3-3-1-3-4 k333 / 3-3-3-2-3 → fk 14
+//! P2: 1-2-3-4-5 k12345 → ls 40
+//! ```
+//!
+//! Every turn line lists the numbers of each throw, kept numbers first, and the numbers
+//! kept after it. The arrow is followed by the box, its column with more than one column,
+//! and the points written. `->` may be used instead of the arrow, lines starting with `#`
+//! are comments.
+
+use super::record::{GameRecord, TurnRecord, RECORD_VERSION};
+use super::{utils, Ruleset, ScoreType};
+
+const ARROW: &str = "→";
+
+fn join_numbers(numbers: &[i32], separator: &str) -> String {
+    return numbers
+        .iter()
+        .map(|num| num.to_string())
+        .collect::<Vec<String>>()
+        .join(separator);
+}
+
+fn format_keep(kept: &[i32], rules: &Ruleset) -> String {
+    // single digits are written without separator, e.g. k33
+    if rules.n_faces <= 9 {
+        return format!("k{}", join_numbers(kept, ""));
+    }
+    return format!("k{}", join_numbers(kept, "-"));
+}
+
+fn format_turn(turn: &TurnRecord, rules: &Ruleset) -> String {
+    let mut throws = Vec::with_capacity(turn.rolls.len());
+    let mut kept: &[i32] = &[];
+    for (throw, roll) in turn.rolls.iter().enumerate() {
+        let numbers = [kept, roll.as_slice()].concat();
+        let mut text = join_numbers(&numbers, "-");
+        if let Some(next_kept) = turn.keeps.get(throw) {
+            text.push(' ');
+            text.push_str(&format_keep(next_kept, rules));
+            kept = next_kept;
+        }
+        throws.push(text);
+    }
+    let column = match rules.columns.len() {
+        1 => String::new(),
+        _ => format!(" {}", turn.column + 1),
+    };
+    return format!(
+        "P{}: {} {ARROW} {}{column} {}",
+        turn.player + 1,
+        throws.join(" / "),
+        turn.score_type.abbreviation(),
+        turn.points
+    );
+}
+
+/// Write a game in the notation. Only games with the preset rules can be written, the
+/// notation refers to the rules by name.
+pub fn format_game(record: &GameRecord) -> Result<String, String> {
+    if Ruleset::from_name(&record.rules.name).as_ref() != Ok(&record.rules) {
+        return Err(format!(
+            "the notation only supports the preset rules ({})",
+            Ruleset::names().join(", ")
+        ));
+    }
+    let mut lines = vec![format!("[Rules \"{}\"]", record.rules.name)];
+    if let Some(seed) = record.seed {
+        lines.push(format!("[Seed \"{seed}\"]"));
+    }
+    for (i, player) in record.players.iter().enumerate() {
+        lines.push(format!("[P{} \"{player}\"]", i + 1));
+    }
+    lines.push(String::new());
+    for turn in &record.turns {
+        lines.push(format_turn(turn, &record.rules));
+    }
+    return Ok(lines.join("\n") + "\n");
+}
+
+fn parse_header(line: &str) -> Result<(&str, &str), String> {
+    // [Tag "value"]
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .ok_or(String::from("a header looks like [Tag \"value\"]"))?;
+    let (tag, value) = inner
+        .split_once(' ')
+        .ok_or(String::from("a header looks like [Tag \"value\"]"))?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .ok_or(format!("the value of {tag} has to be quoted"))?;
+    return Ok((tag, value));
+}
+
+fn parse_numbers(text: &str, rules: &Ruleset) -> Result<Vec<i32>, String> {
+    let numbers: Vec<i32> = text
+        .split('-')
+        .map(|num| num.parse::<i32>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("{text:?} are not numbers separated by -"))?;
+    if numbers.len() != rules.n_dice {
+        return Err(format!(
+            "{text:?} has {} numbers instead of {}",
+            numbers.len(),
+            rules.n_dice
+        ));
+    }
+    if let Some(num) = numbers
+        .iter()
+        .find(|num| **num < 1 || **num > rules.n_faces)
+    {
+        return Err(format!(
+            "{num} is not on a die with {} faces",
+            rules.n_faces
+        ));
+    }
+    return Ok(numbers);
+}
+
+fn parse_keep(text: &str, rules: &Ruleset) -> Result<Vec<i32>, String> {
+    let digits = text
+        .strip_prefix('k')
+        .ok_or(format!("expected kept numbers like k33, found {text:?}"))?;
+    if digits.is_empty() {
+        return Ok(vec![]);
+    }
+    let parsed: Option<Vec<i32>> = if digits.contains('-') || rules.n_faces > 9 {
+        digits.split('-').map(|num| num.parse().ok()).collect()
+    } else {
+        digits
+            .chars()
+            .map(|digit| digit.to_digit(10).map(|num| num as i32))
+            .collect()
+    };
+    return parsed.ok_or(format!("{text:?} are not kept numbers"));
+}
+
+fn remove_kept(numbers: &[i32], kept: &[i32]) -> Result<Vec<i32>, String> {
+    // the rolled dice of a throw are the numbers without the ones kept before it
+    let mut rolled = numbers.to_vec();
+    for num in kept {
+        match rolled.iter().position(|rolled_num| rolled_num == num) {
+            Some(index) => rolled.remove(index),
+            None => {
+                return Err(format!(
+                    "{} does not contain the kept {}",
+                    join_numbers(numbers, "-"),
+                    join_numbers(kept, "-")
+                ))
+            }
+        };
+    }
+    return Ok(rolled);
+}
+
+fn parse_turn(
+    line: &str,
+    rules: &Ruleset,
+    turn: usize,
+    player: usize,
+) -> Result<TurnRecord, String> {
+    let (prefix, rest) = line
+        .split_once(':')
+        .ok_or(String::from("a turn starts with the player, e.g. P1:"))?;
+    if prefix.trim() != format!("P{}", player + 1) {
+        return Err(format!(
+            "expected a turn of P{}, found {:?}",
+            player + 1,
+            prefix.trim()
+        ));
+    }
+    let (throws, choice) = rest
+        .split_once(ARROW)
+        .or_else(|| rest.split_once("->"))
+        .ok_or(format!("missing {ARROW} before the chosen box"))?;
+
+    let mut rolls = Vec::new();
+    let mut keeps: Vec<Vec<i32>> = Vec::new();
+    let throws: Vec<&str> = throws.split('/').collect();
+    for (i, throw) in throws.iter().enumerate() {
+        let words: Vec<&str> = throw.split_whitespace().collect();
+        let (numbers, keep) = match words[..] {
+            [numbers] => (numbers, None),
+            [numbers, keep] => (numbers, Some(keep)),
+            _ => {
+                return Err(format!(
+                    "expected numbers and a keep, found {:?}",
+                    throw.trim()
+                ))
+            }
+        };
+        let numbers = parse_numbers(numbers, rules)?;
+        rolls.push(remove_kept(
+            &numbers,
+            keeps.last().map_or(&[], |kept| kept),
+        )?);
+        let is_last = i + 1 == throws.len();
+        match keep {
+            Some(keep) => {
+                let kept = parse_keep(keep, rules)?;
+                if is_last && kept.len() != rules.n_dice {
+                    return Err(format!("{keep} needs another throw after it"));
+                }
+                let not_thrown = utils::count_unique_elements(&kept)
+                    .into_iter()
+                    .find(|(num, count)| numbers.iter().filter(|n| *n == num).count() < *count);
+                if let Some((num, _)) = not_thrown {
+                    return Err(format!("{keep} keeps a {num} that was not thrown"));
+                }
+                keeps.push(kept);
+            }
+            None if !is_last => {
+                return Err(format!(
+                    "the numbers kept after {} are missing",
+                    join_numbers(&numbers, "-")
+                ))
+            }
+            None => {}
+        }
+    }
+
+    let words: Vec<&str> = choice.split_whitespace().collect();
+    let (abbreviation, column, points) = match words[..] {
+        [abbreviation, points] if rules.columns.len() == 1 => (abbreviation, "1", points),
+        [abbreviation, column, points] => (abbreviation, column, points),
+        _ if rules.columns.len() == 1 => {
+            return Err(format!("expected box and points after {ARROW}"))
+        }
+        _ => return Err(format!("expected box, column and points after {ARROW}")),
+    };
+    let score_type: ScoreType = *rules
+        .categories
+        .iter()
+        .find(|score_t| score_t.abbreviation() == abbreviation)
+        .ok_or(format!("{abbreviation:?} is not a box of these rules"))?;
+    let column = match column.parse::<usize>() {
+        Ok(column) if column >= 1 && column <= rules.columns.len() => column - 1,
+        _ => return Err(format!("{column:?} is not a column of these rules")),
+    };
+    let points = points
+        .parse::<i32>()
+        .map_err(|_| format!("{points:?} are not points"))?;
+    return Ok(TurnRecord {
+        turn,
+        player,
+        rolls,
+        keeps,
+        column,
+        score_type,
+        points,
+    });
+}
+
+/// Read a game written in the notation. Errors name the line they were found in. The
+/// turns are not played here, `record::replay` checks that they are legal.
+pub fn parse_game(text: &str) -> Result<GameRecord, String> {
+    let mut rules: Option<Ruleset> = None;
+    let mut seed: Option<u64> = None;
+    let mut players: Vec<String> = Vec::new();
+    let mut turns = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        let error = |e: String| format!("line {}: {e}", index + 1);
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            if !turns.is_empty() {
+                return Err(error(String::from("headers have to come before the turns")));
+            }
+            let (tag, value) = parse_header(line).map_err(error)?;
+            match tag {
+                "Rules" => rules = Some(Ruleset::from_name(value).map_err(error)?),
+                "Seed" => {
+                    let parsed = value.parse().map_err(|_| format!("invalid seed {value}"));
+                    seed = Some(parsed.map_err(error)?);
+                }
+                _ if tag.strip_prefix('P').and_then(|n| n.parse::<usize>().ok())
+                    == Some(players.len() + 1) =>
+                {
+                    players.push(String::from(value));
+                }
+                _ => {
+                    return Err(error(format!(
+                        "unknown header {tag}, expected Rules, Seed or P{}",
+                        players.len() + 1
+                    )))
+                }
+            }
+            continue;
+        }
+        let rules = rules
+            .as_ref()
+            .ok_or(error(String::from("the Rules header is missing")))?;
+        if players.is_empty() {
+            return Err(error(String::from("the P1 header is missing")));
+        }
+        let n = turns.len();
+        let turn =
+            parse_turn(line, rules, n / players.len() + 1, n % players.len()).map_err(error)?;
+        turns.push(turn);
+    }
+    return Ok(GameRecord {
+        version: RECORD_VERSION,
+        rules: rules.ok_or(String::from("the Rules header is missing"))?,
+        seed,
+        players,
+        turns,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::record::replay;
+    use crate::engine::{play_game_headless, Dice, GreedyBot, RandomBot, Seat};
+
+    const GAME: &str = "[Rules \"kniffel\"]
+[P1 \"alice\"]
+
+P1: 2-3-3-5-6 k33 / 3-3-1-3-4 k333 / 3-3-3-2-3 → fk 14
+P1: 1-2-3-4-5 k12345 -> ls 40
+";
+
+    #[test]
+    fn parse_and_replay() {
+        let record = parse_game(GAME).unwrap();
+        assert_eq!(record.players, ["alice"]);
+        let turn = &record.turns[0];
+        assert_eq!(turn.rolls, [vec![2, 3, 3, 5, 6], vec![1, 3, 4], vec![2, 3]]);
+        assert_eq!(turn.keeps, [vec![3, 3], vec![3, 3, 3]]);
+        assert_eq!((turn.score_type, turn.points), (ScoreType::FourOfAKind, 14));
+        assert_eq!(record.turns[1].rolls.len(), 1);
+        let game = replay(&record, |_, _| {}).unwrap();
+        assert_eq!(game.results(), [(String::from("alice"), 54)]);
+    }
+
+    #[test]
+    fn format_and_parse() {
+        let seats: Vec<Seat> = vec![
+            (String::from("easy"), Box::new(RandomBot::new(5))),
+            (String::from("greedy"), Box::new(GreedyBot)),
+        ];
+        let game = play_game_headless(seats, Dice::seeded(2), Ruleset::kniffel_block()).unwrap();
+        let record = GameRecord::from_game(&game);
+        let text = format_game(&record).unwrap();
+        assert!(text.starts_with("[Rules \"block\"]\n[Seed \"2\"]\n[P1 \"easy\"]"));
+        let parsed = parse_game(&text).unwrap();
+        assert_eq!(format_game(&parsed).unwrap(), text);
+        assert_eq!(
+            replay(&parsed, |_, _| {}).unwrap().results(),
+            game.results()
+        );
+
+        let custom = GameRecord {
+            rules: Ruleset::kniffel().with_throws(4),
+            ..record
+        };
+        assert!(format_game(&custom).is_err());
+    }
+
+    #[test]
+    fn errors() {
+        let error = |text: &str| parse_game(text).unwrap_err();
+        assert_eq!(
+            error("P1: 1-2-3-4-5 → ls 40"),
+            "line 1: the Rules header is missing"
+        );
+        assert_eq!(
+            error("[Rules \"kniffel\"]\n[P2 \"bob\"]"),
+            "line 2: unknown header P2, expected Rules, Seed or P1"
+        );
+        let header = "[Rules \"kniffel\"]\n[P1 \"alice\"]\n";
+        let turn_error = |turn: &str| error(&format!("{header}{turn}"));
+        assert_eq!(
+            turn_error("P2: 1-2-3-4-5 → ls 40"),
+            "line 3: expected a turn of P1, found \"P2\""
+        );
+        assert_eq!(
+            turn_error("P1: 1-2-3-4 → ls 40"),
+            "line 3: \"1-2-3-4\" has 4 numbers instead of 5"
+        );
+        assert_eq!(
+            turn_error("P1: 1-2-3-4-5 k12 → ls 40"),
+            "line 3: k12 needs another throw after it"
+        );
+        assert_eq!(
+            turn_error("P1: 1-2-3-4-5 k66 / 6-6-1-1-1 → c 20"),
+            "line 3: k66 keeps a 6 that was not thrown"
+        );
+        assert_eq!(
+            turn_error("P1: 1-2-3-4-5 k5 / 6-6-1-1-1 → c 20"),
+            "line 3: 6-6-1-1-1 does not contain the kept 5"
+        );
+        assert_eq!(
+            turn_error("P1: 1-2-3-4-5 / 6-6-1-1-1 → c 20"),
+            "line 3: the numbers kept after 1-2-3-4-5 are missing"
+        );
+        assert_eq!(
+            turn_error("P1: 1-2-3-4-5 → xx 40"),
+            "line 3: \"xx\" is not a box of these rules"
+        );
+        assert_eq!(
+            turn_error("P1: 1-2-3-4-5 ls 40"),
+            "line 3: missing → before the chosen box"
+        );
+    }
+}
@@ -0,0 +1,528 @@
+use std::io;
+use std::path::Path;
+
+use super::solver::Solver;
+use super::{potential_score, score_gain, ColumnOrder, GameState, ScoreType};
+
+/// Number of choices listed by the hint command.
+const N_HINTS: usize = 5;
+/// File written by the save command if no file is given.
+const DEFAULT_SAVE_FILE: &str = "kniffel-save.json";
+
+/// Produces the lines printed by the hint command.
+type Hints = fn(&mut Solver, &GameState) -> Result<Vec<String>, String>;
+
+/// Makes the decisions of one seat in a game, e.g. a human at the console or a bot.
+pub trait Player {
+    /// Return the numbers (not their indices) to keep from `game.numbers()`.
+    fn decide_keep_dice(&mut self, game: &GameState) -> Vec<i32>;
+
+    /// Return which dice of `game.numbers()` to hold for the next throw, by position.
+    /// An `Err` is reported and the player is asked again. Holds the dice with the numbers
+    /// of `decide_keep_dice` by default.
+    fn decide_held_dice(&mut self, game: &GameState) -> Result<Vec<bool>, String> {
+        let kept_numbers = self.decide_keep_dice(game);
+        return game.held_for(&kept_numbers);
+    }
+
+    /// Return the column (starting at 0) and score type to write `game.numbers()` to.
+    /// An `Err` is reported and the player is asked again.
+    fn decide_scoresheet_update(&mut self, game: &GameState) -> Result<(usize, ScoreType), String>;
+
+    /// Return the column and score type to announce after the first throw, or `None` to
+    /// not use an announced column this turn. Only asked if `game.can_announce()`.
+    /// Announces the first open box if nothing else is left to fill.
+    fn decide_announcement(&mut self, game: &GameState) -> Option<(usize, ScoreType)> {
+        if !game.must_announce() {
+            return None;
+        }
+        let rules = game.rules();
+        for (column, sheet) in game.current_score_card().columns().iter().enumerate() {
+            for score_t in &rules.categories {
+                if sheet.is_fillable(*score_t, rules.columns[column].order, rules) {
+                    return Some((column, *score_t));
+                }
+            }
+        }
+        return None;
+    }
+}
+
+fn read_line() -> String {
+    let mut input = String::new();
+    io::stdin()
+        .read_line(&mut input)
+        .expect("Failed to read line");
+    return input;
+}
+
+fn is_hint(input: &str) -> bool {
+    return input.trim().eq_ignore_ascii_case("hint");
+}
+
+fn commands(game: &GameState) -> &'static str {
+    // hint is only offered if the solver can play the rules
+    match Solver::supports(game.rules()) {
+        Ok(_) => return "Type hint for advice or save [file] to save the game",
+        Err(_) => return "Type save [file] to save the game",
+    }
+}
+
+fn save_file(input: &str) -> Option<&str> {
+    // "save" or "save <file>"
+    let mut words = input.split_whitespace();
+    if !words.next()?.eq_ignore_ascii_case("save") {
+        return None;
+    }
+    return Some(words.next().unwrap_or(DEFAULT_SAVE_FILE));
+}
+
+fn parse_held(game: &GameState, input: &str) -> Result<Vec<bool>, String> {
+    // "1 3 5" holds or releases dice by position, "x.x.x" marks all held dice and
+    // "keep 3 3 5" keeps dice by their numbers. An empty line leaves the holds as they
+    // are, on the first throw of a turn nothing is held, so all dice are thrown again
+    let input = input.trim().to_lowercase();
+    let n_dice = game.numbers().len();
+    if let Some(numbers) = input.strip_prefix("keep") {
+        let kept_numbers = numbers
+            .split_whitespace()
+            .map(|word| word.parse().map_err(|_| format!("{word} is not a number")))
+            .collect::<Result<Vec<i32>, String>>()?;
+        return game.held_for(&kept_numbers);
+    }
+    if !input.is_empty() && input.chars().all(|c| c == 'x' || c == '.') {
+        if input.len() != n_dice {
+            return Err(format!("mark all {n_dice} dice with x or ."));
+        }
+        return Ok(input.chars().map(|c| c == 'x').collect());
+    }
+    // numbers that cannot be positions were most likely meant as values, e.g. "3 3 5"
+    let mut held = game.held().to_vec();
+    let mut toggled = vec![false; n_dice];
+    for word in input.split_whitespace() {
+        match word.parse::<usize>() {
+            Ok(die) if die >= 1 && die <= n_dice && !toggled[die - 1] => {
+                toggled[die - 1] = true;
+                held[die - 1] = !held[die - 1];
+            }
+            Ok(_) => {
+                return Err(format!(
+                    "{input} are not positions of different dice, type keep {input} to keep dice by their numbers"
+                ));
+            }
+            Err(_) => return Err(format!("there is no die {word}")),
+        }
+    }
+    return Ok(held);
+}
+
+fn format_dice(numbers: &[i32], held: &[bool]) -> String {
+    // the positions above the numbers, held dice in brackets
+    let mut positions = String::from("die   ");
+    let mut dice = String::from("      ");
+    for (die, (num, held)) in numbers.iter().zip(held).enumerate() {
+        positions.push_str(&format!("{:^6}", die + 1));
+        match held {
+            true => dice.push_str(&format!("{:^6}", format!("[{num}]"))),
+            false => dice.push_str(&format!("{num:^6}")),
+        }
+    }
+    return format!("{positions}\n{dice}");
+}
+
+fn parse_box(game: &GameState, input: String) -> Result<(usize, ScoreType), String> {
+    // parse "abbreviation [column]", the column is 1-based and optional with one column
+    let n_columns = game.rules().columns.len();
+    let lowercase_input = input.to_lowercase();
+    let mut words = lowercase_input.split_whitespace();
+    let abbreviation = words.next().unwrap_or_default();
+    let column = match (words.next(), n_columns) {
+        (None, 1) => 0,
+        (Some(word), _) => match word.parse::<usize>() {
+            Ok(column) if column >= 1 && column <= n_columns => column - 1,
+            _ => return Err(input),
+        },
+        (None, _) => return Err(input),
+    };
+    match game
+        .rules()
+        .categories
+        .iter()
+        .find(|score_t| score_t.abbreviation() == abbreviation)
+    {
+        Some(score_t) => return Ok((column, *score_t)),
+        None => return Err(input),
+    }
+}
+
+fn potential_points(game: &GameState) -> Vec<String> {
+    // one line for every box the numbers may be written to, boxes that would add nothing
+    // to the total are scratched
+    let rules = game.rules();
+    let several_columns = rules.columns.len() > 1;
+    let mut lines = vec![format!("{:<8}{:<25}{:>6}", "box", "", "points")];
+    for (column, score_t) in game.legal_boxes() {
+        let sheet = &game.current_score_card().columns()[column];
+        let points = potential_score(sheet, score_t, game.numbers(), rules);
+        let name = match several_columns {
+            true => format!("{} {}", score_t.abbreviation(), column + 1),
+            false => score_t.abbreviation().to_string(),
+        };
+        let mut line = format!("{name:<8}{:<25}{points:>6}", score_t.name());
+        if score_gain(sheet, score_t, game.numbers(), rules) == 0 {
+            line.push_str("  scratch");
+        }
+        lines.push(line);
+    }
+    return lines;
+}
+
+fn format_hints<T: std::fmt::Display>(ranked: &[(T, f64)], sum: i32) -> Vec<String> {
+    // the first few choices with the expected final score and the loss against the best one
+    let best = match ranked.first() {
+        Some((_, best)) => *best,
+        None => return vec![],
+    };
+    return ranked
+        .iter()
+        .take(N_HINTS)
+        .map(|(choice, value)| {
+            let expected = sum as f64 + value;
+            if *value == best {
+                return format!("{choice}: {expected:.1} expected (best)");
+            }
+            return format!("{choice}: {expected:.1} expected ({:.1})", value - best);
+        })
+        .collect();
+}
+
+/// Hints for keeping dice, ranked by expected final score.
+pub fn keep_hints(solver: &mut Solver, game: &GameState) -> Result<Vec<String>, String> {
+    let sheet = &game.current_score_card().columns()[0];
+    let ranked: Vec<(String, f64)> = solver
+        .ranked_keeps(sheet, game.numbers(), game.throws_left())?
+        .into_iter()
+        .map(|(keep, value)| (format!("keep {keep:?}"), value))
+        .collect();
+    return Ok(format_hints(&ranked, sheet.sum(game.rules())));
+}
+
+/// Hints for the box to write the numbers to, ranked by expected final score.
+pub fn category_hints(solver: &mut Solver, game: &GameState) -> Result<Vec<String>, String> {
+    let sheet = &game.current_score_card().columns()[0];
+    let ranked: Vec<(String, f64)> = solver
+        .ranked_categories(sheet, game.numbers())?
+        .into_iter()
+        .map(|(score_t, value)| {
+            let points = potential_score(sheet, score_t, game.numbers(), game.rules());
+            return (
+                format!("{} ({points} points)", score_t.abbreviation()),
+                value,
+            );
+        })
+        .collect();
+    return Ok(format_hints(&ranked, sheet.sum(game.rules())));
+}
+
+/// A human player who is asked for every decision on stdin.
+#[derive(Default)]
+pub struct HumanPlayer {
+    /// created on the first hint, solving takes a while
+    solver: Option<Solver>,
+}
+
+impl HumanPlayer {
+    pub fn new() -> HumanPlayer {
+        return HumanPlayer::default();
+    }
+
+    fn print_hints(&mut self, game: &GameState, hints: Hints) {
+        if Solver::supports(game.rules()).is_err() {
+            println!("hints are not available for these rules");
+            return;
+        }
+        if self
+            .solver
+            .as_ref()
+            .is_none_or(|solver| solver.rules() != game.rules())
+        {
+            match Solver::new(game.rules().clone()) {
+                Ok(solver) => self.solver = Some(solver),
+                Err(e) => {
+                    println!("no hints available: {e}");
+                    return;
+                }
+            }
+            println!("computing the best strategy, this takes a while the first time");
+        }
+        let solver = self.solver.as_mut().expect("created above");
+        match hints(solver, game) {
+            Ok(lines) => {
+                for line in lines {
+                    println!("  {line}");
+                }
+            }
+            Err(e) => println!("no hints available: {e}"),
+        }
+    }
+
+    fn read_command(&mut self, game: &GameState, hints: Option<Hints>) -> String {
+        // answer hint and save commands until the input is something else
+        loop {
+            let input = read_line();
+            if let Some(path) = save_file(&input) {
+                match game.save(Path::new(path)) {
+                    Ok(_) => {
+                        println!("game saved to {path}, continue it later with --resume {path}")
+                    }
+                    Err(e) => println!("{e}"),
+                }
+                continue;
+            }
+            match hints {
+                Some(hints) if is_hint(&input) => self.print_hints(game, hints),
+                _ => return input,
+            }
+        }
+    }
+}
+
+impl Player for HumanPlayer {
+    fn decide_keep_dice(&mut self, game: &GameState) -> Vec<i32> {
+        loop {
+            match self.decide_held_dice(game) {
+                Ok(held) => {
+                    return game
+                        .numbers()
+                        .iter()
+                        .zip(held)
+                        .filter(|(_, held)| *held)
+                        .map(|(num, _)| *num)
+                        .collect()
+                }
+                Err(e) => println!("{e}"),
+            }
+        }
+    }
+
+    fn decide_held_dice(&mut self, game: &GameState) -> Result<Vec<bool>, String> {
+        println!("{}", format_dice(game.numbers(), game.held()));
+        println!("Enter the positions of dice to hold or release them, e.g. 1 3 5, or mark the dice to hold like x.x.x. To keep dice by their numbers type keep and the numbers, e.g. keep 3 3 5, or just keep to throw all dice again. Press enter to keep the holds as they are and throw the other dice. {}", commands(game));
+        let input = self.read_command(game, Some(keep_hints));
+        return parse_held(game, &input);
+    }
+
+    fn decide_scoresheet_update(&mut self, game: &GameState) -> Result<(usize, ScoreType), String> {
+        let numbers = game.numbers();
+        println!("your numbers are {numbers:?}");
+        for line in potential_points(game) {
+            println!("{line}");
+        }
+        if game.rules().columns.len() == 1 {
+            println!(
+                "pick the score type to write to, e.g. \"fh\". {}",
+                commands(game)
+            );
+        } else {
+            println!(
+                "pick score type and column to write to, e.g. \"fh 2\". {}",
+                commands(game)
+            );
+        }
+        loop {
+            let input = self.read_command(game, Some(category_hints));
+            let (column, score_t) = parse_box(game, input)?;
+            let sheet = &game.current_score_card().columns()[column];
+            if !game.legal_boxes().contains(&(column, score_t))
+                || score_gain(sheet, score_t, numbers, game.rules()) > 0
+            {
+                return Ok((column, score_t));
+            }
+            println!(
+                "{} adds 0 points with these numbers. type y to scratch it or anything else to pick another box",
+                score_t.name()
+            );
+            if read_line().trim().eq_ignore_ascii_case("y") {
+                return Ok((column, score_t));
+            }
+            println!("pick another box");
+        }
+    }
+
+    fn decide_announcement(&mut self, game: &GameState) -> Option<(usize, ScoreType)> {
+        let numbers = game.numbers();
+        println!("your numbers are {numbers:?}");
+        let rules = game.rules();
+        for (column, sheet) in game.current_score_card().columns().iter().enumerate() {
+            if rules.columns[column].order != ColumnOrder::Announced {
+                continue;
+            }
+            let open: Vec<&str> = rules
+                .categories
+                .iter()
+                .filter(|score_t| sheet.is_fillable(**score_t, ColumnOrder::Announced, rules))
+                .map(|score_t| score_t.abbreviation())
+                .collect();
+            println!(
+                "column {} can be announced: {}",
+                column + 1,
+                open.join(", ")
+            );
+        }
+        if game.must_announce() {
+            println!("announce the score type and column, e.g. \"fh 4\"");
+        } else {
+            println!(
+                "announce the score type and column, e.g. \"fh 4\", or press enter to not announce"
+            );
+        }
+        loop {
+            let input = self.read_command(game, None);
+            if input.trim().is_empty() {
+                return None;
+            }
+            match parse_box(game, input) {
+                Ok(announcement) => return Some(announcement),
+                Err(e) => println!("invalid announcement {}, try again", e.trim()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Dice, Ruleset};
+
+    #[test]
+    fn save_command() {
+        assert_eq!(save_file("save\n"), Some(DEFAULT_SAVE_FILE));
+        assert_eq!(save_file("Save game.json"), Some("game.json"));
+        assert_eq!(save_file("1 2 3"), None);
+        assert_eq!(save_file(""), None);
+    }
+
+    #[test]
+    fn held_dice_input() {
+        let mut game = GameState::new(
+            vec![String::from("alice")],
+            Dice::scripted(vec![3, 5, 3, 1, 6]),
+            Ruleset::kniffel(),
+        );
+        game.roll().unwrap();
+        // nothing is held after the first throw, so enter throws all dice again
+        assert_eq!(parse_held(&game, "\n"), Ok(vec![false; 5]));
+        game.hold(vec![true, false, false, false, false]).unwrap();
+        assert_eq!(
+            parse_held(&game, "1 3 5\n"),
+            Ok(vec![false, false, true, false, true])
+        );
+        assert_eq!(parse_held(&game, "").unwrap(), game.held());
+        assert_eq!(
+            parse_held(&game, "x.X.."),
+            Ok(vec![true, false, true, false, false])
+        );
+        assert_eq!(
+            parse_held(&game, "keep 6 3"),
+            Ok(vec![true, false, false, false, true])
+        );
+        assert_eq!(parse_held(&game, "keep"), Ok(vec![false; 5]));
+        assert_eq!(
+            parse_held(&game, "3 3 5"),
+            Err(String::from(
+                "3 3 5 are not positions of different dice, type keep 3 3 5 to keep dice by their numbers"
+            ))
+        );
+        assert!(parse_held(&game, "6").is_err());
+        assert!(parse_held(&game, "one").is_err());
+        assert!(parse_held(&game, "x.x").is_err());
+        assert!(parse_held(&game, "keep 5 5").is_err());
+        assert_eq!(
+            format_dice(game.numbers(), game.held()),
+            "die     1     2     3     4     5   \n       [3]    5     3     1     6   "
+        );
+    }
+
+    #[test]
+    fn potential_points_table() {
+        let rules = Ruleset {
+            categories: vec![ScoreType::Twos, ScoreType::FullHouse, ScoreType::Chance],
+            ..Ruleset::kniffel()
+        };
+        let mut game = GameState::new(
+            vec![String::from("alice")],
+            Dice::scripted(vec![2, 3, 5, 5, 5]),
+            rules,
+        );
+        game.roll().unwrap();
+        assert_eq!(
+            potential_points(&game),
+            [
+                "box                              points",
+                "2       Twos                          2",
+                "fh      Full House                    0  scratch",
+                "c       Chance                       20",
+            ]
+        );
+    }
+
+    #[test]
+    fn joker_is_not_scratched() {
+        let rules = Ruleset {
+            categories: vec![ScoreType::Ones, ScoreType::Fours, ScoreType::Yahtzee],
+            ..Ruleset::hasbro_yahtzee()
+        };
+        let mut game = GameState::new(
+            vec![String::from("alice")],
+            Dice::scripted(vec![4; 15]),
+            rules,
+        );
+        game.roll().unwrap();
+        game.choose_category(0, ScoreType::Yahtzee).unwrap();
+        game.advance_player().unwrap();
+        game.roll().unwrap();
+        game.choose_category(0, ScoreType::Fours).unwrap();
+        game.advance_player().unwrap();
+        game.roll().unwrap();
+        // Ones scores nothing but the Yahtzee bonus is worth keeping
+        assert_eq!(
+            potential_points(&game),
+            [
+                "box                              points",
+                "1       Ones                          0",
+            ]
+        );
+    }
+
+    #[test]
+    fn hints() {
+        let rules = Ruleset {
+            categories: vec![ScoreType::Sixes, ScoreType::Yahtzee, ScoreType::Chance],
+            ..Ruleset::kniffel().with_dice(3, 6)
+        };
+        let mut solver = Solver::new(rules.clone()).unwrap();
+        let mut game = GameState::new(
+            vec![String::from("alice")],
+            Dice::scripted(vec![6, 6, 1]),
+            rules,
+        );
+        game.roll().unwrap();
+        let hints = keep_hints(&mut solver, &game).unwrap();
+        assert_eq!(hints.len(), N_HINTS);
+        assert!(hints[0].starts_with("keep [6, 6]: "));
+        assert!(hints[0].ends_with("(best)"));
+        assert!(hints[1].contains("(-"));
+
+        let hints = category_hints(&mut solver, &game).unwrap();
+        assert_eq!(hints.len(), 3);
+        assert!(hints.iter().any(|hint| hint.starts_with("y (0 points)")));
+
+        let block = GameState::new(
+            vec![String::from("alice")],
+            Dice::seeded(1),
+            Ruleset::kniffel_block(),
+        );
+        assert!(commands(&game).contains("hint"));
+        assert!(!commands(&block).contains("hint"));
+    }
+}
@@ -0,0 +1,240 @@
+use std::fmt;
+
+use super::dice::DiceSets;
+use super::{detect_full_house, is_large_straight, is_small_straight, is_yahtzee, utils, Ruleset};
+
+/// A combination of dice the engine recognizes at the end of a turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Combination {
+    FullHouse,
+    /// four consecutive numbers
+    SmallStraight,
+    /// five consecutive numbers
+    LargeStraight,
+    ThreeOfAKind,
+    FourOfAKind,
+    /// all dice show the same number
+    Yahtzee,
+    /// at least `count` dice show `face`
+    AtLeast {
+        count: usize,
+        face: i32,
+    },
+}
+
+impl Combination {
+    pub fn is_reached(&self, numbers: &[i32], rules: &Ruleset) -> bool {
+        match self {
+            Combination::FullHouse => return detect_full_house(numbers).is_some(),
+            Combination::SmallStraight => return is_small_straight(numbers, rules),
+            Combination::LargeStraight => return is_large_straight(numbers, rules),
+            Combination::ThreeOfAKind => return max_count(numbers) >= 3,
+            Combination::FourOfAKind => return max_count(numbers) >= 4,
+            Combination::Yahtzee => return is_yahtzee(numbers, rules),
+            Combination::AtLeast { count, face } => {
+                return numbers.iter().filter(|num| *num == face).count() >= *count;
+            }
+        }
+    }
+
+    /// Parse e.g. "fh", "ss", "ls", "tk", "fk", "y" or "3x6" for at least three sixes.
+    pub fn parse(text: &str) -> Result<Combination, String> {
+        match text.trim().to_lowercase().as_str() {
+            "fh" => return Ok(Combination::FullHouse),
+            "ss" => return Ok(Combination::SmallStraight),
+            "ls" => return Ok(Combination::LargeStraight),
+            "tk" => return Ok(Combination::ThreeOfAKind),
+            "fk" => return Ok(Combination::FourOfAKind),
+            "y" => return Ok(Combination::Yahtzee),
+            other => {
+                let parsed = other
+                    .split_once('x')
+                    .and_then(|(count, face)| Some((count.parse().ok()?, face.parse().ok()?)));
+                match parsed {
+                    Some((count, face)) => return Ok(Combination::AtLeast { count, face }),
+                    None => return Err(format!("unknown combination {text}")),
+                }
+            }
+        }
+    }
+
+    /// The combinations without a face and "at least n" of every face for n from 1 to
+    /// the number of dice.
+    pub fn all(rules: &Ruleset) -> Vec<Combination> {
+        let mut all = vec![
+            Combination::FullHouse,
+            Combination::SmallStraight,
+            Combination::LargeStraight,
+            Combination::ThreeOfAKind,
+            Combination::FourOfAKind,
+            Combination::Yahtzee,
+        ];
+        for face in 1..=rules.n_faces {
+            for count in 1..=rules.n_dice {
+                all.push(Combination::AtLeast { count, face });
+            }
+        }
+        return all;
+    }
+}
+
+impl fmt::Display for Combination {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Combination::FullHouse => return write!(f, "full house"),
+            Combination::SmallStraight => return write!(f, "small straight"),
+            Combination::LargeStraight => return write!(f, "large straight"),
+            Combination::ThreeOfAKind => return write!(f, "three of a kind"),
+            Combination::FourOfAKind => return write!(f, "four of a kind"),
+            Combination::Yahtzee => return write!(f, "yahtzee"),
+            Combination::AtLeast { count, face } => return write!(f, "at least {count} x {face}"),
+        }
+    }
+}
+
+fn max_count(numbers: &[i32]) -> usize {
+    return utils::count_unique_elements(numbers)
+        .into_values()
+        .max()
+        .unwrap_or_default();
+}
+
+/// Calculates the exact probability to end a turn with a combination, when the dice not
+/// kept are thrown next and the dice are kept in the best way for the combination after that.
+pub struct Calculator {
+    rules: Ruleset,
+    dice_sets: DiceSets,
+}
+
+impl Calculator {
+    pub fn new(rules: Ruleset) -> Calculator {
+        let dice_sets = DiceSets::new(rules.n_dice, rules.n_faces);
+        return Calculator { rules, dice_sets };
+    }
+
+    /// `throws_left` counts the next throw. With no throws left the kept dice have to be
+    /// all dice.
+    pub fn probability(
+        &self,
+        combination: Combination,
+        kept: &[i32],
+        throws_left: usize,
+    ) -> Result<f64, String> {
+        if kept.len() > self.rules.n_dice {
+            return Err(format!("there are only {} dice", self.rules.n_dice));
+        }
+        let kept = self
+            .dice_sets
+            .find(kept)
+            .ok_or(format!("{kept:?} are not dice of these rules"))?;
+        let mut values = vec![0.0; self.dice_sets.len()];
+        for throw in self.dice_sets.full_throws() {
+            if combination.is_reached(self.dice_sets.get(throw), &self.rules) {
+                values[throw] = 1.0;
+            }
+        }
+        if throws_left == 0 {
+            if self.dice_sets.get(kept).len() < self.rules.n_dice {
+                return Err(String::from("no throws left for the dice not kept"));
+            }
+            return Ok(values[kept]);
+        }
+        for _ in 1..throws_left {
+            values = self.dice_sets.rethrow_values(&values);
+        }
+        return Ok(self.dice_sets.keep_values(&values)[kept]);
+    }
+
+    /// The probabilities of all combinations in `Combination::all`.
+    pub fn probabilities(
+        &self,
+        kept: &[i32],
+        throws_left: usize,
+    ) -> Result<Vec<(Combination, f64)>, String> {
+        return Combination::all(&self.rules)
+            .into_iter()
+            .map(|combination| {
+                Ok((
+                    combination,
+                    self.probability(combination, kept, throws_left)?,
+                ))
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(Combination::parse("FH"), Ok(Combination::FullHouse));
+        assert_eq!(
+            Combination::parse("3x6"),
+            Ok(Combination::AtLeast { count: 3, face: 6 })
+        );
+        assert!(Combination::parse("6x").is_err());
+        assert!(Combination::parse("poker").is_err());
+    }
+
+    #[test]
+    fn single_throw() {
+        let calculator = Calculator::new(Ruleset::kniffel());
+        let yahtzee = calculator
+            .probability(Combination::Yahtzee, &[], 1)
+            .unwrap();
+        assert!((yahtzee - 6.0 / 7776.0).abs() < 1e-12);
+        let large = calculator
+            .probability(Combination::LargeStraight, &[], 1)
+            .unwrap();
+        assert!((large - 240.0 / 7776.0).abs() < 1e-12);
+        let sixes = calculator
+            .probability(Combination::AtLeast { count: 4, face: 6 }, &[6, 6, 6], 1)
+            .unwrap();
+        assert!((sixes - 11.0 / 36.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn more_throws() {
+        let calculator = Calculator::new(Ruleset::kniffel());
+        let yahtzee = calculator
+            .probability(Combination::Yahtzee, &[], 3)
+            .unwrap();
+        assert!((yahtzee - 0.046029).abs() < 1e-5);
+        let sixes = calculator
+            .probability(Combination::AtLeast { count: 5, face: 6 }, &[6, 6, 6, 6], 2)
+            .unwrap();
+        assert!((sixes - 11.0 / 36.0).abs() < 1e-12);
+        assert_eq!(
+            calculator.probability(Combination::FullHouse, &[2, 2, 2, 5, 5], 0),
+            Ok(1.0)
+        );
+        assert!(calculator
+            .probability(Combination::FullHouse, &[2, 2], 0)
+            .is_err());
+        assert!(calculator
+            .probability(Combination::FullHouse, &[7], 1)
+            .is_err());
+    }
+
+    #[test]
+    fn fixed_straights() {
+        // Yatzy and Yacht only accept 1-5 as the small and 2-6 as the large straight
+        for rules in [Ruleset::yatzy(), Ruleset::yacht()] {
+            let calculator = Calculator::new(rules);
+            let small = calculator
+                .probability(Combination::SmallStraight, &[], 1)
+                .unwrap();
+            assert!((small - 120.0 / 7776.0).abs() < 1e-12);
+            let large = calculator
+                .probability(Combination::LargeStraight, &[], 1)
+                .unwrap();
+            assert!((large - 120.0 / 7776.0).abs() < 1e-12);
+            assert_eq!(
+                calculator.probability(Combination::SmallStraight, &[2, 3, 4, 5, 6], 0),
+                Ok(0.0)
+            );
+        }
+    }
+}
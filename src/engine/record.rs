@@ -0,0 +1,315 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::notation::{format_game, parse_game};
+use super::{print_all_score_sheets, Action, ColumnOrder, Dice, GameState, Ruleset, ScoreType};
+
+/// Version of the record format, increased on incompatible changes.
+pub(crate) const RECORD_VERSION: u32 = 1;
+
+/// One finished turn of a recorded game.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TurnRecord {
+    pub turn: usize,
+    pub player: usize,
+    /// the dice thrown by every throw, without the kept ones
+    pub rolls: Vec<Vec<i32>>,
+    /// the numbers kept after every throw but the last one
+    pub keeps: Vec<Vec<i32>>,
+    pub column: usize,
+    pub score_type: ScoreType,
+    /// points written to the box, without bonuses and column multiplier
+    pub points: i32,
+}
+
+/// Everything that happened in a game, enough to replay it without the original dice.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub version: u32,
+    pub rules: Ruleset,
+    /// the dice seed if the dice were seeded
+    pub seed: Option<u64>,
+    pub players: Vec<String>,
+    pub turns: Vec<TurnRecord>,
+}
+
+impl GameRecord {
+    /// The record of all finished turns of a game, taken from its history.
+    pub fn from_game(game: &GameState) -> GameRecord {
+        let history = game.history();
+        let n_dice = game.rules().n_dice;
+        let mut turns = Vec::new();
+        let mut rolls: Vec<Vec<i32>> = Vec::new();
+        let mut keeps: Vec<Vec<i32>> = Vec::new();
+        for (i, decision) in history.iter().enumerate() {
+            // the dice that were not held are the numbers of the last throw. Older histories
+            // have no held dice, there the kept numbers come first
+            let n_kept = keeps.last().map_or(0, |kept| kept.len());
+            if n_kept < n_dice {
+                let thrown = decision
+                    .numbers
+                    .iter()
+                    .enumerate()
+                    .filter(|(die, _)| !decision.held.get(*die).copied().unwrap_or(*die < n_kept))
+                    .map(|(_, num)| *num);
+                rolls.push(thrown.collect());
+            }
+            let (column, score_t) = match &decision.action {
+                Action::Keep(kept) => {
+                    keeps.push(kept.clone());
+                    continue;
+                }
+                Action::Score(column, score_t) => (*column, *score_t),
+            };
+            // the score card after the decision is the one of the next decision of the
+            // same player, or the current one
+            let card_after = history[i + 1..]
+                .iter()
+                .find(|next| next.player == decision.player)
+                .map_or(&game.score_cards()[decision.player], |next| {
+                    &next.score_card
+                });
+            turns.push(TurnRecord {
+                turn: decision.turn,
+                player: decision.player,
+                rolls: std::mem::take(&mut rolls),
+                keeps: std::mem::take(&mut keeps),
+                column,
+                score_type: score_t,
+                points: card_after.columns()[column]
+                    .get_score(score_t)
+                    .unwrap_or_default(),
+            });
+        }
+        return GameRecord {
+            version: RECORD_VERSION,
+            rules: game.rules().clone(),
+            seed: game.dice_seed(),
+            players: game.players().to_vec(),
+            turns,
+        };
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        return serde_json::to_string_pretty(self).map_err(|e| e.to_string());
+    }
+
+    pub fn from_json(json: &str) -> Result<GameRecord, String> {
+        let record: GameRecord =
+            serde_json::from_str(json).map_err(|e| format!("invalid game record: {e}"))?;
+        if record.version != RECORD_VERSION {
+            return Err(format!(
+                "game record version {} is not supported",
+                record.version
+            ));
+        }
+        return Ok(record);
+    }
+
+    /// Write the record as JSON, or in the notation of `notation` for files ending in .kn.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = match is_notation(path) {
+            true => format_game(self)?,
+            false => self.to_json()?,
+        };
+        return fs::write(path, text)
+            .map_err(|e| format!("could not write {}: {e}", path.display()));
+    }
+
+    pub fn load(path: &Path) -> Result<GameRecord, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("could not read {}: {e}", path.display()))?;
+        match is_notation(path) {
+            true => return parse_game(&text),
+            false => return GameRecord::from_json(&text),
+        }
+    }
+}
+
+fn is_notation(path: &Path) -> bool {
+    return path.extension().is_some_and(|extension| extension == "kn");
+}
+
+fn check_turn(turn: &TurnRecord, rules: &Ruleset) -> Result<(), String> {
+    // the scripted dice of the replay must get exactly the dice the turn throws
+    let all_kept = turn
+        .keeps
+        .last()
+        .is_some_and(|kept| kept.len() == rules.n_dice);
+    let n_throws = if all_kept {
+        turn.keeps.len()
+    } else {
+        turn.keeps.len() + 1
+    };
+    if turn.rolls.len() != n_throws {
+        return Err(format!(
+            "turn {}: {} throws recorded for {} keeps",
+            turn.turn,
+            turn.rolls.len(),
+            turn.keeps.len()
+        ));
+    }
+    for (throw, roll) in turn.rolls.iter().enumerate() {
+        let n_kept = match throw {
+            0 => 0,
+            _ => turn.keeps[throw - 1].len(),
+        };
+        if roll.len() + n_kept != rules.n_dice
+            || roll.iter().any(|num| *num < 1 || *num > rules.n_faces)
+        {
+            return Err(format!(
+                "turn {}: throw {roll:?} does not fit the rules",
+                turn.turn
+            ));
+        }
+    }
+    return Ok(());
+}
+
+/// Play the recorded game again and check that every turn writes the recorded points.
+/// `on_turn` is called after every turn with the game as it is after the turn.
+pub fn replay(
+    record: &GameRecord,
+    mut on_turn: impl FnMut(&GameState, &TurnRecord),
+) -> Result<GameState, String> {
+    for turn in &record.turns {
+        check_turn(turn, &record.rules)?;
+    }
+    let rolls = record
+        .turns
+        .iter()
+        .flat_map(|turn| turn.rolls.concat())
+        .collect();
+    let mut game = GameState::new(
+        record.players.clone(),
+        Dice::scripted(rolls),
+        record.rules.clone(),
+    );
+    for turn in &record.turns {
+        if (game.turn(), game.current_player()) != (turn.turn, turn.player) {
+            return Err(format!(
+                "turn {} of player {} is out of order",
+                turn.turn,
+                turn.player + 1
+            ));
+        }
+        game.roll()?;
+        // boxes of announced columns were announced right after the first throw
+        if record.rules.columns.get(turn.column).map(|c| c.order) == Some(ColumnOrder::Announced) {
+            game.announce(turn.column, turn.score_type)?;
+        }
+        for (throw, kept) in turn.keeps.iter().enumerate() {
+            game.keep(kept.clone())
+                .map_err(|e| format!("turn {}: {e}", turn.turn))?;
+            if throw + 1 < turn.rolls.len() {
+                game.roll()?;
+            }
+        }
+        let points = game
+            .choose_category(turn.column, turn.score_type)
+            .map_err(|e| format!("turn {}: {e}", turn.turn))?;
+        if points != turn.points {
+            return Err(format!(
+                "turn {}: {:?} scores {points} points, the record says {}",
+                turn.turn, turn.score_type, turn.points
+            ));
+        }
+        on_turn(&game, turn);
+        game.advance_player()?;
+    }
+    return Ok(game);
+}
+
+/// Replay a recorded game on the console, turn by turn.
+pub fn print_replay(record: &GameRecord) {
+    println!(
+        "{} with {} rules{}",
+        record.players.join(", "),
+        record.rules.name,
+        record
+            .seed
+            .map_or(String::new(), |seed| format!(", dice seed {seed}"))
+    );
+    let result = replay(record, |game, turn| {
+        println!(
+            "turn {} for player {}",
+            turn.turn,
+            game.players()[turn.player]
+        );
+        for (throw, roll) in turn.rolls.iter().enumerate() {
+            match turn.keeps.get(throw) {
+                Some(kept) => println!("throw {throw}: {roll:?}, keeping {kept:?}"),
+                None => println!("throw {throw}: {roll:?}"),
+            }
+        }
+        println!(
+            "writing {:?} in column {} for {} points",
+            turn.score_type,
+            turn.column + 1,
+            turn.points
+        );
+        print_all_score_sheets(game);
+        println!("press enter for the next turn");
+        let mut input = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read line");
+    });
+    match result {
+        Ok(game) => {
+            println!("all turns reproduce the recorded points. final result:");
+            for (player, sum) in game.results() {
+                println!("{player}: {sum}")
+            }
+        }
+        Err(e) => println!("the record does not replay: {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{play_game_headless, GreedyBot, RandomBot, Seat};
+
+    #[test]
+    fn record_and_replay() {
+        let seats: Vec<Seat> = vec![
+            (String::from("easy"), Box::new(RandomBot::new(2))),
+            (String::from("greedy"), Box::new(GreedyBot)),
+        ];
+        let game = play_game_headless(seats, Dice::seeded(8), Ruleset::kniffel_block()).unwrap();
+        let record = GameRecord::from_game(&game);
+        assert_eq!(record.seed, Some(8));
+        assert_eq!(record.turns.len(), 2 * game.rules().n_turns());
+        let record = GameRecord::from_json(&record.to_json().unwrap()).unwrap();
+
+        let mut n_turns = 0;
+        let replayed = replay(&record, |_, _| n_turns += 1).unwrap();
+        assert_eq!(n_turns, record.turns.len());
+        assert_eq!(replayed.results(), game.results());
+    }
+
+    #[test]
+    fn invalid_records() {
+        let seats: Vec<Seat> = vec![(String::from("greedy"), Box::new(GreedyBot))];
+        let game = play_game_headless(seats, Dice::seeded(1), Ruleset::kniffel()).unwrap();
+        let record = GameRecord::from_game(&game);
+
+        let mut wrong_points = record.clone();
+        wrong_points.turns[3].points += 1;
+        assert!(replay(&wrong_points, |_, _| {})
+            .err()
+            .unwrap()
+            .contains("the record says"));
+
+        let mut missing_throw = record.clone();
+        missing_throw.turns[0].rolls.clear();
+        assert!(replay(&missing_throw, |_, _| {}).is_err());
+
+        let mut swapped = record;
+        swapped.turns.swap(0, 1);
+        assert!(replay(&swapped, |_, _| {}).is_err());
+    }
+}
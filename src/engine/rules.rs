@@ -0,0 +1,461 @@
+use serde::{Deserialize, Serialize};
+
+use super::ScoreType;
+
+/// How a category with a fixed shape (e.g. the full house) is scored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Points {
+    Fixed(i32),
+    /// the sum of all dice
+    Sum,
+}
+
+/// Which dice count as a straight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Straights {
+    /// small: any four consecutive numbers, large: any five consecutive numbers
+    Runs,
+    /// small: exactly 1-2-3-4-5, large: exactly 2-3-4-5-6
+    Fixed,
+}
+
+/// Which dice are summed up for the "of a kind" categories.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KindPoints {
+    AllDice,
+    /// only the dice that form the kind, e.g. 3 x 4 for three fours
+    MatchedDice,
+}
+
+/// In which order the boxes of a column may be filled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColumnOrder {
+    Free,
+    /// strictly from the top of the sheet downwards
+    TopDown,
+    /// strictly from the bottom of the sheet upwards
+    BottomUp,
+    /// only the box that was announced after the first throw of the turn
+    Announced,
+}
+
+/// One column of the score sheet. Every player fills all columns of the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Column {
+    /// the points of the column count this many times
+    pub multiplier: i32,
+    pub order: ColumnOrder,
+}
+
+impl Column {
+    pub fn free(multiplier: i32) -> Column {
+        Column {
+            multiplier,
+            order: ColumnOrder::Free,
+        }
+    }
+
+    /// Parse "free", "down", "up" or "ann", optionally with a multiplier like "down:2".
+    pub fn from_name(name: &str) -> Result<Column, String> {
+        let (order, multiplier) = match name.trim().split_once(':') {
+            Some((order, multiplier)) => match multiplier.parse::<i32>() {
+                Ok(multiplier) if multiplier >= 1 => (order, multiplier),
+                _ => return Err(format!("invalid multiplier in column {name}")),
+            },
+            None => (name.trim(), 1),
+        };
+        let order = match order.to_lowercase().as_str() {
+            "free" => ColumnOrder::Free,
+            "down" => ColumnOrder::TopDown,
+            "up" => ColumnOrder::BottomUp,
+            "ann" => ColumnOrder::Announced,
+            _ => return Err(format!("unknown column {name}, use free, down, up or ann")),
+        };
+        return Ok(Column { multiplier, order });
+    }
+
+    /// Short description for the header of the printed score sheet.
+    pub fn label(&self) -> String {
+        match self.order {
+            ColumnOrder::Free => return format!("x{}", self.multiplier),
+            ColumnOrder::TopDown => return format!("down x{}", self.multiplier),
+            ColumnOrder::BottomUp => return format!("up x{}", self.multiplier),
+            ColumnOrder::Announced => return format!("ann x{}", self.multiplier),
+        }
+    }
+}
+
+/// The scoring constants and category behaviors of a game variant.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ruleset {
+    pub name: String,
+    pub n_dice: usize,
+    /// every die shows a number from 1 to `n_faces`
+    pub n_faces: i32,
+    /// throws per turn
+    pub n_throws: usize,
+    /// throws not used in a turn can be used in later turns of the same player
+    pub carry_over_throws: bool,
+    /// the boxes of the score sheet, in the order they are printed
+    pub categories: Vec<ScoreType>,
+    pub columns: Vec<Column>,
+    pub upper_bonus_threshold: i32,
+    /// 0 if there is no upper bonus
+    pub upper_bonus: i32,
+    pub kind_points: KindPoints,
+    pub full_house: Points,
+    pub straights: Straights,
+    pub small_straight: i32,
+    pub large_straight: i32,
+    pub yahtzee: i32,
+    /// points for every additional Yahtzee while the Yahtzee box holds full points, 0 if none
+    pub yahtzee_bonus: i32,
+    /// an additional Yahtzee is a joker with forced placement
+    pub joker_rules: bool,
+    pub yahtzee_counts_as_full_house: bool,
+}
+
+impl Ruleset {
+    /// German Kniffel: a Kniffel also counts as full house, no bonus for extra Kniffels.
+    pub fn kniffel() -> Ruleset {
+        Ruleset {
+            name: String::from("kniffel"),
+            columns: vec![Column::free(1)],
+            n_dice: 5,
+            n_faces: 6,
+            n_throws: 3,
+            carry_over_throws: false,
+            categories: vec![
+                ScoreType::Ones,
+                ScoreType::Twos,
+                ScoreType::Threes,
+                ScoreType::Fours,
+                ScoreType::Fives,
+                ScoreType::Sixes,
+                ScoreType::ThreeOfAKind,
+                ScoreType::FourOfAKind,
+                ScoreType::FullHouse,
+                ScoreType::SmallStraight,
+                ScoreType::LargeStraight,
+                ScoreType::Yahtzee,
+                ScoreType::Chance,
+            ],
+            upper_bonus_threshold: 63,
+            upper_bonus: 35,
+            kind_points: KindPoints::AllDice,
+            full_house: Points::Fixed(25),
+            straights: Straights::Runs,
+            small_straight: 30,
+            large_straight: 40,
+            yahtzee: 50,
+            yahtzee_bonus: 0,
+            joker_rules: false,
+            yahtzee_counts_as_full_house: true,
+        }
+    }
+
+    /// Hasbro Yahtzee with the extra Yahtzee bonus and joker rules.
+    pub fn hasbro_yahtzee() -> Ruleset {
+        Ruleset {
+            name: String::from("yahtzee"),
+            yahtzee_bonus: 100,
+            joker_rules: true,
+            yahtzee_counts_as_full_house: false,
+            ..Ruleset::kniffel()
+        }
+    }
+
+    /// The original Yacht: no upper bonus, no three of a kind, full house scores the sum,
+    /// four of a kind only the four matching dice and both straights are fixed.
+    pub fn yacht() -> Ruleset {
+        Ruleset {
+            name: String::from("yacht"),
+            columns: vec![Column::free(1)],
+            n_dice: 5,
+            n_faces: 6,
+            n_throws: 3,
+            carry_over_throws: false,
+            categories: vec![
+                ScoreType::Ones,
+                ScoreType::Twos,
+                ScoreType::Threes,
+                ScoreType::Fours,
+                ScoreType::Fives,
+                ScoreType::Sixes,
+                ScoreType::FullHouse,
+                ScoreType::FourOfAKind,
+                ScoreType::SmallStraight,
+                ScoreType::LargeStraight,
+                ScoreType::Chance,
+                ScoreType::Yahtzee,
+            ],
+            upper_bonus_threshold: 0,
+            upper_bonus: 0,
+            kind_points: KindPoints::MatchedDice,
+            full_house: Points::Sum,
+            straights: Straights::Fixed,
+            small_straight: 30,
+            large_straight: 30,
+            yahtzee: 50,
+            yahtzee_bonus: 0,
+            joker_rules: false,
+            yahtzee_counts_as_full_house: false,
+        }
+    }
+
+    /// Scandinavian Yatzy: pairs categories, kinds and straights only count the matching
+    /// dice, full house is the sum and the upper bonus is 50.
+    pub fn yatzy() -> Ruleset {
+        Ruleset {
+            name: String::from("yatzy"),
+            columns: vec![Column::free(1)],
+            n_dice: 5,
+            n_faces: 6,
+            n_throws: 3,
+            carry_over_throws: false,
+            categories: vec![
+                ScoreType::Ones,
+                ScoreType::Twos,
+                ScoreType::Threes,
+                ScoreType::Fours,
+                ScoreType::Fives,
+                ScoreType::Sixes,
+                ScoreType::OnePair,
+                ScoreType::TwoPairs,
+                ScoreType::ThreeOfAKind,
+                ScoreType::FourOfAKind,
+                ScoreType::SmallStraight,
+                ScoreType::LargeStraight,
+                ScoreType::FullHouse,
+                ScoreType::Chance,
+                ScoreType::Yahtzee,
+            ],
+            upper_bonus_threshold: 63,
+            upper_bonus: 50,
+            kind_points: KindPoints::MatchedDice,
+            full_house: Points::Sum,
+            straights: Straights::Fixed,
+            small_straight: 15,
+            large_straight: 20,
+            yahtzee: 50,
+            yahtzee_bonus: 0,
+            joker_rules: false,
+            yahtzee_counts_as_full_house: false,
+        }
+    }
+
+    /// Maxi Yatzy: Yatzy with six dice, more categories, 100 points for six of a kind
+    /// and unused throws are saved for later turns.
+    pub fn maxi_yatzy() -> Ruleset {
+        Ruleset {
+            name: String::from("maxi"),
+            n_dice: 6,
+            carry_over_throws: true,
+            categories: vec![
+                ScoreType::Ones,
+                ScoreType::Twos,
+                ScoreType::Threes,
+                ScoreType::Fours,
+                ScoreType::Fives,
+                ScoreType::Sixes,
+                ScoreType::OnePair,
+                ScoreType::TwoPairs,
+                ScoreType::ThreePairs,
+                ScoreType::ThreeOfAKind,
+                ScoreType::FourOfAKind,
+                ScoreType::FiveOfAKind,
+                ScoreType::SmallStraight,
+                ScoreType::LargeStraight,
+                ScoreType::FullStraight,
+                ScoreType::FullHouse,
+                ScoreType::Castle,
+                ScoreType::Tower,
+                ScoreType::Chance,
+                ScoreType::Yahtzee,
+            ],
+            upper_bonus_threshold: 84,
+            upper_bonus: 50,
+            yahtzee: 100,
+            ..Ruleset::yatzy()
+        }
+    }
+
+    /// Triple Yahtzee: Hasbro rules with three columns that count once, twice and three times.
+    pub fn triple_yahtzee() -> Ruleset {
+        Ruleset {
+            name: String::from("triple"),
+            ..Ruleset::hasbro_yahtzee().with_columns(3)
+        }
+    }
+
+    /// Play `n_columns` columns, the first counts once, the second twice and so on.
+    pub fn with_columns(self, n_columns: usize) -> Ruleset {
+        let columns = (1..=n_columns as i32).map(Column::free).collect();
+        return Ruleset { columns, ..self };
+    }
+
+    /// Play with `n_dice` dice that have `n_faces` faces each. Panics without dice or faces.
+    pub fn with_dice(self, n_dice: usize, n_faces: i32) -> Ruleset {
+        assert!(n_dice >= 1, "there has to be at least one die");
+        assert!(n_faces >= 1, "dice need at least one face");
+        return Ruleset {
+            n_dice,
+            n_faces,
+            ..self
+        };
+    }
+
+    /// Allow `n_throws` throws per turn. Panics for zero throws.
+    pub fn with_throws(self, n_throws: usize) -> Ruleset {
+        assert!(n_throws >= 1, "there has to be at least one throw per turn");
+        return Ruleset { n_throws, ..self };
+    }
+
+    /// Check that a game can be played with these rules, e.g. rules read from a file.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.n_dice == 0 {
+            return Err(String::from("there has to be at least one die"));
+        }
+        if self.n_faces < 1 {
+            return Err(String::from("dice need at least one face"));
+        }
+        if self.n_throws == 0 {
+            return Err(String::from("there has to be at least one throw per turn"));
+        }
+        if self.columns.is_empty() || self.categories.is_empty() {
+            return Err(String::from("there has to be at least one box to write to"));
+        }
+        return Ok(());
+    }
+
+    /// The German Kniffel block with a column to fill downwards, one upwards, a free one
+    /// and one where the box has to be announced after the first throw.
+    pub fn kniffel_block() -> Ruleset {
+        let columns = [
+            ColumnOrder::TopDown,
+            ColumnOrder::BottomUp,
+            ColumnOrder::Free,
+            ColumnOrder::Announced,
+        ]
+        .into_iter()
+        .map(|order| Column {
+            multiplier: 1,
+            order,
+        })
+        .collect();
+        Ruleset {
+            name: String::from("block"),
+            columns,
+            ..Ruleset::kniffel()
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Ruleset, String> {
+        match name.to_lowercase().trim() {
+            "kniffel" => return Ok(Ruleset::kniffel()),
+            "yahtzee" => return Ok(Ruleset::hasbro_yahtzee()),
+            "yacht" => return Ok(Ruleset::yacht()),
+            "yatzy" => return Ok(Ruleset::yatzy()),
+            "maxi" => return Ok(Ruleset::maxi_yatzy()),
+            "triple" => return Ok(Ruleset::triple_yahtzee()),
+            "block" => return Ok(Ruleset::kniffel_block()),
+            _ => return Err(format!("unknown rules {name}")),
+        }
+    }
+
+    pub fn names() -> Vec<&'static str> {
+        return vec![
+            "kniffel", "yahtzee", "yacht", "yatzy", "maxi", "triple", "block",
+        ];
+    }
+
+    pub fn n_turns(&self) -> usize {
+        return self.categories.len() * self.columns.len();
+    }
+}
+
+impl Default for Ruleset {
+    fn default() -> Ruleset {
+        return Ruleset::kniffel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_by_name() {
+        for name in Ruleset::names() {
+            assert_eq!(Ruleset::from_name(name).unwrap().name, name);
+        }
+        assert_eq!(Ruleset::from_name(" Yacht\n"), Ok(Ruleset::yacht()));
+        assert!(Ruleset::from_name("poker").is_err());
+    }
+
+    #[test]
+    fn column_multipliers() {
+        let multipliers: Vec<i32> = Ruleset::triple_yahtzee()
+            .columns
+            .iter()
+            .map(|column| column.multiplier)
+            .collect();
+        assert_eq!(multipliers, vec![1, 2, 3]);
+        assert_eq!(Ruleset::kniffel().columns, vec![Column::free(1)]);
+    }
+
+    #[test]
+    fn column_names() {
+        assert_eq!(Column::from_name("free"), Ok(Column::free(1)));
+        assert_eq!(
+            Column::from_name("Down:3"),
+            Ok(Column {
+                multiplier: 3,
+                order: ColumnOrder::TopDown
+            })
+        );
+        assert!(Column::from_name("up:0").is_err());
+        assert!(Column::from_name("sideways").is_err());
+    }
+
+    #[test]
+    fn dice_and_throws() {
+        let rules = Ruleset::kniffel().with_dice(4, 8).with_throws(4);
+        assert_eq!((rules.n_dice, rules.n_faces, rules.n_throws), (4, 8, 4));
+        assert_eq!(rules.categories, Ruleset::kniffel().categories);
+        assert_eq!(Ruleset::maxi_yatzy().n_faces, 6);
+        assert!(rules.validate().is_ok());
+        let no_throws = Ruleset {
+            n_throws: 0,
+            ..Ruleset::kniffel()
+        };
+        assert!(no_throws.validate().is_err());
+        let no_faces = Ruleset {
+            n_faces: 0,
+            ..Ruleset::kniffel()
+        };
+        assert!(no_faces.validate().is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one throw")]
+    fn zero_throws() {
+        Ruleset::kniffel().with_throws(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one face")]
+    fn zero_faces() {
+        Ruleset::kniffel().with_dice(5, 0);
+    }
+
+    #[test]
+    fn number_of_turns() {
+        assert_eq!(Ruleset::yacht().n_turns(), 12);
+        assert_eq!(Ruleset::kniffel().n_turns(), 13);
+        assert_eq!(Ruleset::yatzy().n_turns(), 15);
+        assert_eq!(Ruleset::maxi_yatzy().n_turns(), 20);
+        assert_eq!(Ruleset::triple_yahtzee().n_turns(), 39);
+        assert_eq!(Ruleset::kniffel_block().n_turns(), 52);
+    }
+}
@@ -0,0 +1,186 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use std::sync::{Arc, Mutex};
+
+use super::solver::Solver;
+use super::{create_bot, play_game_headless, Dice, Difficulty, Ruleset, ScoreCard, ScoreType};
+
+/// Statistics of many games of one strategy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub n_games: usize,
+    pub mean: f64,
+    pub std_dev: f64,
+    /// the score of every percentile in `PERCENTILES`
+    pub percentiles: Vec<(usize, i32)>,
+    /// share of columns that reached the upper bonus
+    pub upper_bonus_rate: f64,
+    /// share of columns with full points in the Yahtzee box
+    pub yahtzee_rate: f64,
+    /// average points of every box, over all columns
+    pub category_averages: Vec<(ScoreType, f64)>,
+}
+
+pub const PERCENTILES: [usize; 5] = [5, 25, 50, 75, 95];
+
+impl Summary {
+    pub fn from_cards(cards: &[ScoreCard], rules: &Ruleset) -> Result<Summary, String> {
+        let n_games = cards.len();
+        if n_games == 0 {
+            return Err(String::from("at least one game is needed"));
+        }
+        let mut sums: Vec<i32> = cards.iter().map(|card| card.sum(rules)).collect();
+        sums.sort();
+        let mean = sums.iter().sum::<i32>() as f64 / n_games as f64;
+        let squares: f64 = sums.iter().map(|sum| (*sum as f64 - mean).powi(2)).sum();
+        let std_dev = (squares / (n_games.max(2) - 1) as f64).sqrt();
+        let percentiles = PERCENTILES
+            .iter()
+            .map(|percentile| {
+                // nearest rank
+                let rank = (percentile * n_games).div_ceil(100).max(1);
+                return (*percentile, sums.get(rank - 1).copied().unwrap_or_default());
+            })
+            .collect();
+
+        let sheets: Vec<_> = cards.iter().flat_map(|card| card.columns()).collect();
+        let n_sheets = sheets.len().max(1) as f64;
+        let upper_bonus_rate = sheets
+            .iter()
+            .filter(|sheet| sheet.upper_bonus(rules) > 0)
+            .count() as f64
+            / n_sheets;
+        let yahtzee_rate = sheets
+            .iter()
+            .filter(|sheet| sheet.get_score(ScoreType::Yahtzee) == Some(rules.yahtzee))
+            .count() as f64
+            / n_sheets;
+        let category_averages = rules
+            .categories
+            .iter()
+            .map(|score_t| {
+                let total: i32 = sheets
+                    .iter()
+                    .map(|sheet| sheet.get_score(*score_t).unwrap_or_default())
+                    .sum();
+                return (*score_t, total as f64 / n_sheets);
+            })
+            .collect();
+        return Ok(Summary {
+            n_games,
+            mean,
+            std_dev,
+            percentiles,
+            upper_bonus_rate,
+            yahtzee_rate,
+            category_averages,
+        });
+    }
+}
+
+/// A solver with all states solved if one of the bots plays optimally. Solving once up
+/// front lets every thread work on its own copy of the solved states.
+pub(crate) fn solve_for(
+    difficulties: &[Difficulty],
+    rules: &Ruleset,
+) -> Result<Option<Solver>, String> {
+    if !difficulties
+        .iter()
+        .any(|difficulty| matches!(difficulty, Difficulty::Optimal { .. }))
+    {
+        return Ok(None);
+    }
+    let mut solver = Solver::new(rules.clone())?;
+    solver.expected_final_score(&ScoreCard::new(rules).columns()[0]);
+    return Ok(Some(solver));
+}
+
+/// Play `n_games` solitaire games of a bot on all CPU cores. The results only depend on
+/// the seed, not on the number of cores.
+pub fn simulate(
+    difficulty: Difficulty,
+    rules: &Ruleset,
+    n_games: usize,
+    seed: u64,
+) -> Result<Summary, String> {
+    if n_games == 0 {
+        return Err(String::from("at least one game is needed"));
+    }
+    let solver = solve_for(&[difficulty], rules)?;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let seeds: Vec<(u64, u64)> = (0..n_games).map(|_| (rng.gen(), rng.gen())).collect();
+    let cards: Vec<ScoreCard> = seeds
+        .into_par_iter()
+        .map_init(
+            || solver.clone().map(|solver| Arc::new(Mutex::new(solver))),
+            |solver, (dice_seed, bot_seed)| {
+                let bot = create_bot(difficulty, bot_seed, solver.clone())?;
+                let seats = vec![(String::from("bot"), bot)];
+                let game = play_game_headless(seats, Dice::seeded(dice_seed), rules.clone())?;
+                return Ok(game.score_cards()[0].clone());
+            },
+        )
+        .collect::<Result<_, String>>()?;
+    return Summary::from_cards(&cards, rules);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small_rules() -> Ruleset {
+        return Ruleset {
+            categories: vec![
+                ScoreType::Fives,
+                ScoreType::Sixes,
+                ScoreType::FullHouse,
+                ScoreType::Yahtzee,
+                ScoreType::Chance,
+            ],
+            upper_bonus_threshold: 18,
+            ..Ruleset::kniffel().with_dice(4, 6)
+        };
+    }
+
+    #[test]
+    fn summary() {
+        let rules = small_rules();
+        let mut cards = Vec::new();
+        for sixes in [1, 2, 3, 4] {
+            let mut card = ScoreCard::new(&rules);
+            let sheet = card.column_mut(0).unwrap();
+            sheet.set_score(ScoreType::Sixes, 6 * sixes);
+            sheet.set_score(ScoreType::Yahtzee, if sixes == 4 { 50 } else { 0 });
+            cards.push(card);
+        }
+        let summary = Summary::from_cards(&cards, &rules).unwrap();
+        assert_eq!(summary.n_games, 4);
+        // sums are 6, 12, 18 + 35 and 24 + 50 + 35
+        assert_eq!(summary.mean, (6.0 + 12.0 + 53.0 + 109.0) / 4.0);
+        assert_eq!(summary.percentiles[0], (5, 6));
+        assert_eq!(summary.percentiles[2], (50, 12));
+        assert_eq!(summary.percentiles[4], (95, 109));
+        assert_eq!(summary.upper_bonus_rate, 0.5);
+        assert_eq!(summary.yahtzee_rate, 0.25);
+        assert_eq!(summary.category_averages[1], (ScoreType::Sixes, 15.0));
+    }
+
+    #[test]
+    fn deterministic_and_ranked() {
+        let rules = small_rules();
+        let easy = simulate(Difficulty::Easy, &rules, 200, 1).unwrap();
+        assert_eq!(easy, simulate(Difficulty::Easy, &rules, 200, 1).unwrap());
+        assert_ne!(easy, simulate(Difficulty::Easy, &rules, 200, 2).unwrap());
+        let greedy = simulate(Difficulty::Greedy, &rules, 200, 1).unwrap();
+        let optimal = simulate(Difficulty::Optimal { noise: 0.0 }, &rules, 200, 1).unwrap();
+        assert_eq!(
+            optimal,
+            simulate(Difficulty::Optimal { noise: 0.0 }, &rules, 200, 1).unwrap()
+        );
+        assert!(easy.mean < greedy.mean);
+        assert!(simulate(Difficulty::Easy, &rules, 0, 1).is_err());
+        assert!(Summary::from_cards(&[], &rules).is_err());
+        assert!(greedy.mean < optimal.mean);
+    }
+}
@@ -0,0 +1,485 @@
+use std::collections::HashMap;
+
+use super::dice::DiceSets;
+use super::{
+    check_score_type, is_yahtzee, potential_score, update_score_sheet, ColumnOrder, Ruleset,
+    ScoreSheet, ScoreType,
+};
+
+/// The parts of a score sheet that matter for the rest of a solitaire game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SheetState {
+    /// bit i is set if `rules.categories[i]` is written
+    filled: u32,
+    /// sum of the upper half, capped at the bonus threshold
+    upper_sum: i32,
+    /// the Yahtzee box holds full points, so every further Yahtzee earns the bonus
+    yahtzee_bonus: bool,
+}
+
+impl SheetState {
+    pub fn from_sheet(sheet: &ScoreSheet, rules: &Ruleset) -> SheetState {
+        let mut filled = 0;
+        for (i, score_t) in rules.categories.iter().enumerate() {
+            if sheet.is_already_written(*score_t) {
+                filled |= 1 << i;
+            }
+        }
+        return SheetState {
+            filled,
+            upper_sum: sheet.upper_sum().min(upper_cap(rules)),
+            yahtzee_bonus: rules.yahtzee_bonus > 0
+                && sheet.get_score(ScoreType::Yahtzee) == Some(rules.yahtzee),
+        };
+    }
+
+    fn is_filled(&self, category: usize) -> bool {
+        return self.filled & (1 << category) != 0;
+    }
+}
+
+fn upper_cap(rules: &Ruleset) -> i32 {
+    // upper sums above the threshold do not change the outcome anymore
+    if rules.upper_bonus > 0 {
+        return rules.upper_bonus_threshold;
+    }
+    return 0;
+}
+
+/// Exact expected-score-maximizing strategy for a single player, computed by dynamic
+/// programming over sheet states and the throws within a turn. Values of sheet states are
+/// computed when first needed and cached, so the first query of a full game takes a while.
+#[derive(Clone)]
+pub struct Solver {
+    rules: Ruleset,
+    dice_sets: DiceSets,
+    /// points of category i for every full throw, without and with the Yahtzee box written
+    points: [Vec<Vec<i32>>; 2],
+    upper_bonus_at: Vec<i32>,
+    yahtzee_bonus_points: i32,
+    /// position of the Yahtzee box in the categories, if played
+    yahtzee_category: Option<usize>,
+    /// for every dice set whether it is a Yahtzee
+    yahtzees: Vec<bool>,
+    values: HashMap<SheetState, f64>,
+}
+
+impl Solver {
+    /// Check that the solver can play the rules, without solving them yet. Only games
+    /// with a single free column and without saved throws are supported.
+    pub fn supports(rules: &Ruleset) -> Result<(), String> {
+        rules.validate()?;
+        if rules.columns.len() != 1 || rules.columns[0].order != ColumnOrder::Free {
+            return Err(String::from(
+                "the solver supports a single free column only",
+            ));
+        }
+        if rules.carry_over_throws {
+            return Err(String::from("the solver does not support saved throws"));
+        }
+        if rules.categories.len() > 32 {
+            return Err(String::from("the solver supports at most 32 categories"));
+        }
+        return Ok(());
+    }
+
+    /// Precompute the scores of the rules, see `supports` for the rules that are supported.
+    pub fn new(rules: Ruleset) -> Result<Solver, String> {
+        Solver::supports(&rules)?;
+
+        let dice_sets = DiceSets::new(rules.n_dice, rules.n_faces);
+
+        let mut points = [vec![], vec![]];
+        for (yahtzee_written, table) in points.iter_mut().enumerate() {
+            let mut sheet = ScoreSheet::new();
+            if yahtzee_written == 1 {
+                sheet.set_score(ScoreType::Yahtzee, 0);
+            }
+            for score_t in &rules.categories {
+                let mut row = vec![0; dice_sets.len()];
+                for throw in dice_sets.full_throws() {
+                    row[throw] = potential_score(&sheet, *score_t, dice_sets.get(throw), &rules);
+                }
+                table.push(row);
+            }
+        }
+        let upper_bonus_at = (0..=upper_cap(&rules))
+            .map(|upper_sum| {
+                let mut sheet = ScoreSheet::new();
+                sheet.set_score(ScoreType::Ones, upper_sum);
+                return sheet.upper_bonus(&rules);
+            })
+            .collect();
+        let mut sheet = ScoreSheet::new();
+        sheet.set_score(ScoreType::Yahtzee, rules.yahtzee);
+        update_score_sheet(
+            &mut sheet,
+            ScoreType::Chance,
+            &vec![1; rules.n_dice],
+            &rules,
+        );
+        let yahtzee_bonus_points = sheet.yahtzee_bonus(&rules);
+        let yahtzee_category = rules
+            .categories
+            .iter()
+            .position(|score_t| *score_t == ScoreType::Yahtzee);
+        let yahtzees = (0..dice_sets.len())
+            .map(|set| is_yahtzee(dice_sets.get(set), &rules))
+            .collect();
+
+        return Ok(Solver {
+            rules,
+            dice_sets,
+            points,
+            upper_bonus_at,
+            yahtzee_bonus_points,
+            yahtzee_category,
+            yahtzees,
+            values: HashMap::new(),
+        });
+    }
+
+    pub fn rules(&self) -> &Ruleset {
+        return &self.rules;
+    }
+
+    /// Expected points still to be scored from the state on when playing optimally.
+    pub fn state_value(&mut self, state: SheetState) -> f64 {
+        if let Some(value) = self.values.get(&state) {
+            return *value;
+        }
+        let all_filled = (0..self.rules.categories.len()).all(|i| state.is_filled(i));
+        let value = if all_filled {
+            0.0
+        } else {
+            let values = self.throw_values(state, self.rules.n_throws - 1);
+            self.dice_sets.keep_values(&values)[0]
+        };
+        self.values.insert(state, value);
+        return value;
+    }
+
+    /// Expected final sum of the sheet when playing optimally from here, before the next turn.
+    pub fn expected_final_score(&mut self, sheet: &ScoreSheet) -> f64 {
+        let state = SheetState::from_sheet(sheet, &self.rules);
+        return sheet.sum(&self.rules) as f64 + self.state_value(state);
+    }
+
+    /// The best numbers to keep from `numbers` with `throws_left` throws left in the turn
+    /// and the expected points still to come with that choice.
+    pub fn best_keep(
+        &mut self,
+        sheet: &ScoreSheet,
+        numbers: &[i32],
+        throws_left: usize,
+    ) -> Result<(Vec<i32>, f64), String> {
+        let mut ranked = self.ranked_keeps(sheet, numbers, throws_left)?;
+        return Ok(ranked.swap_remove(0));
+    }
+
+    /// All different choices of numbers to keep with the expected points still to come,
+    /// best first.
+    pub fn ranked_keeps(
+        &mut self,
+        sheet: &ScoreSheet,
+        numbers: &[i32],
+        throws_left: usize,
+    ) -> Result<Vec<(Vec<i32>, f64)>, String> {
+        if throws_left == 0 {
+            return Err(String::from("no throws left"));
+        }
+        let roll = self.roll_index(numbers)?;
+        let state = SheetState::from_sheet(sheet, &self.rules);
+        let values = self.throw_values(state, throws_left - 1);
+        let keep_values = self.dice_sets.keep_values(&values);
+        let mut ranked: Vec<(Vec<i32>, f64)> = self
+            .dice_sets
+            .keeps(roll)
+            .iter()
+            .map(|keep| (self.dice_sets.get(*keep).to_vec(), keep_values[*keep]))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        return Ok(ranked);
+    }
+
+    /// The best box to write `numbers` to and the expected points still to come with that
+    /// choice, including the points of the box.
+    pub fn best_category(
+        &mut self,
+        sheet: &ScoreSheet,
+        numbers: &[i32],
+    ) -> Result<(ScoreType, f64), String> {
+        let roll = self.roll_index(numbers)?;
+        let state = SheetState::from_sheet(sheet, &self.rules);
+        return self
+            .best_box(state, roll, &mut vec![vec![]; self.rules.categories.len()])
+            .map(|(category, value)| (self.rules.categories[category], value))
+            .ok_or(String::from("no box left to write to"));
+    }
+
+    /// All boxes `numbers` can be written to with the expected points still to come, best
+    /// first.
+    pub fn ranked_categories(
+        &mut self,
+        sheet: &ScoreSheet,
+        numbers: &[i32],
+    ) -> Result<Vec<(ScoreType, f64)>, String> {
+        let mut ranked = Vec::new();
+        for score_t in self.rules.categories.clone() {
+            if let Some(value) = self.category_value(sheet, numbers, score_t)? {
+                ranked.push((score_t, value));
+            }
+        }
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        return Ok(ranked);
+    }
+
+    /// Expected points still to come when writing `numbers` to `score_t` and playing
+    /// optimally afterwards, `None` if the box cannot be written.
+    pub fn category_value(
+        &mut self,
+        sheet: &ScoreSheet,
+        numbers: &[i32],
+        score_t: ScoreType,
+    ) -> Result<Option<f64>, String> {
+        let roll = self.roll_index(numbers)?;
+        let state = SheetState::from_sheet(sheet, &self.rules);
+        let category = self
+            .rules
+            .categories
+            .iter()
+            .position(|category| *category == score_t)
+            .ok_or(format!("{score_t:?} is not played with these rules"))?;
+        if !self.is_legal(state, category, roll) {
+            return Ok(None);
+        }
+        let (points, next) = self.score_box(state, category, roll);
+        return Ok(Some(points as f64 + self.state_value(next)));
+    }
+
+    fn roll_index(&self, numbers: &[i32]) -> Result<usize, String> {
+        match self.dice_sets.find(numbers) {
+            Some(roll) if numbers.len() == self.rules.n_dice => return Ok(roll),
+            _ => return Err(format!("{numbers:?} is not a throw of these rules")),
+        }
+    }
+
+    fn is_legal(&self, state: SheetState, category: usize, roll: usize) -> bool {
+        if state.is_filled(category) {
+            return false;
+        }
+        if !(self.rules.joker_rules && self.yahtzees[roll] && self.yahtzee_written(state)) {
+            return true;
+        }
+        // the forced placement of a joker depends on the open boxes
+        let mut sheet = ScoreSheet::new();
+        for (i, score_t) in self.rules.categories.iter().enumerate() {
+            if state.is_filled(i) {
+                sheet.set_score(*score_t, 0);
+            }
+        }
+        let score_t = self.rules.categories[category];
+        return check_score_type(&sheet, score_t, self.dice_sets.get(roll), &self.rules).is_ok();
+    }
+
+    fn yahtzee_written(&self, state: SheetState) -> bool {
+        return self
+            .yahtzee_category
+            .is_some_and(|category| state.is_filled(category));
+    }
+
+    fn score_box(&self, state: SheetState, category: usize, roll: usize) -> (i32, SheetState) {
+        // points including bonuses for writing the roll to the category and the next state
+        let score_t = self.rules.categories[category];
+        let box_points = self.points[self.yahtzee_written(state) as usize][category][roll];
+        let mut points = box_points;
+        let mut upper_sum = state.upper_sum;
+        if score_t.is_upper() {
+            upper_sum = (upper_sum + box_points).min(upper_cap(&self.rules));
+            points += self.upper_bonus_at[upper_sum as usize]
+                - self.upper_bonus_at[state.upper_sum as usize];
+        }
+        if state.yahtzee_bonus && self.yahtzees[roll] {
+            points += self.yahtzee_bonus_points;
+        }
+        let next = SheetState {
+            filled: state.filled | (1 << category),
+            upper_sum,
+            yahtzee_bonus: state.yahtzee_bonus
+                || (self.rules.yahtzee_bonus > 0
+                    && score_t == ScoreType::Yahtzee
+                    && box_points == self.rules.yahtzee),
+        };
+        return (points, next);
+    }
+
+    fn best_box(
+        &mut self,
+        state: SheetState,
+        roll: usize,
+        next_values: &mut [Vec<(SheetState, f64)>],
+    ) -> Option<(usize, f64)> {
+        // next_values remembers the values of the few different next states per category,
+        // which saves most lookups of `values`
+        let mut best: Option<(usize, f64)> = None;
+        for (category, next_values) in next_values.iter_mut().enumerate() {
+            if !self.is_legal(state, category, roll) {
+                continue;
+            }
+            let (points, next) = self.score_box(state, category, roll);
+            let known = next_values
+                .iter()
+                .find(|(known, _)| *known == next)
+                .map(|(_, value)| *value);
+            let next_value = match known {
+                Some(value) => value,
+                None => {
+                    let value = self.state_value(next);
+                    next_values.push((next, value));
+                    value
+                }
+            };
+            let value = points as f64 + next_value;
+            if best.is_none_or(|(_, best_value)| value > best_value) {
+                best = Some((category, value));
+            }
+        }
+        return best;
+    }
+
+    fn throw_values(&mut self, state: SheetState, throws_left: usize) -> Vec<f64> {
+        // expected points still to come for every full throw with `throws_left` throws left
+        let mut values = vec![0.0; self.dice_sets.len()];
+        let throws: Vec<usize> = self.dice_sets.full_throws().collect();
+        let mut next_values = vec![vec![]; self.rules.categories.len()];
+        for throw in throws {
+            values[throw] = self
+                .best_box(state, throw, &mut next_values)
+                .map_or(0.0, |(_, value)| value);
+        }
+        for _ in 0..throws_left {
+            values = self.dice_sets.rethrow_values(&values);
+        }
+        return values;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Dice;
+
+    fn only(categories: Vec<ScoreType>) -> Ruleset {
+        return Ruleset {
+            categories,
+            ..Ruleset::kniffel()
+        };
+    }
+
+    #[test]
+    fn chance() {
+        let rules = only(vec![ScoreType::Chance]).with_dice(1, 6);
+        let mut solver = Solver::new(rules.clone().with_throws(1)).unwrap();
+        assert!((solver.expected_final_score(&ScoreSheet::new()) - 3.5).abs() < 1e-9);
+        let mut solver = Solver::new(rules.clone().with_throws(2)).unwrap();
+        assert!((solver.expected_final_score(&ScoreSheet::new()) - 4.25).abs() < 1e-9);
+
+        // every die is thrown again below 5 and then below 4
+        let mut solver = Solver::new(only(vec![ScoreType::Chance])).unwrap();
+        let expected = 5.0 * (2.0 / 6.0 * 5.5 + 4.0 / 6.0 * 4.25);
+        assert!((solver.expected_final_score(&ScoreSheet::new()) - expected).abs() < 1e-9);
+        let sheet = ScoreSheet::new();
+        assert_eq!(
+            solver.best_keep(&sheet, &[1, 4, 5, 6, 6], 2).unwrap().0,
+            vec![5, 6, 6]
+        );
+        assert_eq!(
+            solver.best_keep(&sheet, &[1, 4, 5, 6, 6], 1).unwrap().0,
+            vec![4, 5, 6, 6]
+        );
+    }
+
+    #[test]
+    fn yahtzee_probability() {
+        let rules = Ruleset {
+            yahtzee: 1,
+            ..only(vec![ScoreType::Yahtzee])
+        };
+        let mut solver = Solver::new(rules).unwrap();
+        let probability = solver.expected_final_score(&ScoreSheet::new());
+        assert!((probability - 0.046029).abs() < 1e-5);
+    }
+
+    #[test]
+    fn ranked_choices() {
+        let rules = Ruleset {
+            categories: vec![ScoreType::Sixes, ScoreType::Yahtzee, ScoreType::Chance],
+            ..Ruleset::kniffel().with_dice(3, 6)
+        };
+        let mut solver = Solver::new(rules).unwrap();
+        let sheet = ScoreSheet::new();
+        let numbers = [6, 6, 6];
+        let ranked = solver.ranked_categories(&sheet, &numbers).unwrap();
+        assert_eq!(ranked.len(), 3);
+        assert_eq!(ranked[0].0, ScoreType::Yahtzee);
+        assert!(ranked.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        assert_eq!(solver.best_category(&sheet, &numbers).unwrap(), ranked[0]);
+
+        let ranked = solver.ranked_keeps(&sheet, &[1, 6, 6], 2).unwrap();
+        // keeping nothing, 1, 6, 1 6, 6 6 and everything
+        assert_eq!(ranked.len(), 6);
+        assert_eq!(ranked[0].0, vec![6, 6]);
+        assert!(ranked.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        assert!(solver.ranked_keeps(&sheet, &[1, 6, 6], 0).is_err());
+    }
+
+    #[test]
+    fn unsupported_rules() {
+        assert!(Solver::new(Ruleset::triple_yahtzee()).is_err());
+        assert!(Solver::new(Ruleset::kniffel_block()).is_err());
+        assert!(Solver::new(Ruleset::maxi_yatzy()).is_err());
+    }
+
+    #[test]
+    fn consistent_with_score_sheet() {
+        // the expected final score stays the same when following the best choices
+        let rules = Ruleset {
+            upper_bonus_threshold: 5,
+            upper_bonus: 20,
+            ..Ruleset::hasbro_yahtzee().with_dice(3, 4)
+        };
+        let rules = Ruleset {
+            categories: vec![
+                ScoreType::Ones,
+                ScoreType::Twos,
+                ScoreType::ThreeOfAKind,
+                ScoreType::Yahtzee,
+                ScoreType::Chance,
+            ],
+            ..rules
+        };
+        let mut solver = Solver::new(rules.clone()).unwrap();
+        for seed in 0..5 {
+            let mut dice = Dice::seeded(seed);
+            let mut sheet = ScoreSheet::new();
+            while !sheet.is_full(&rules) {
+                let mut numbers = dice.gen_numbers(rules.n_dice, rules.n_faces);
+                for throws_left in (1..rules.n_throws).rev() {
+                    let (keep, _) = solver.best_keep(&sheet, &numbers, throws_left).unwrap();
+                    numbers = keep.clone();
+                    numbers.extend(dice.gen_numbers(rules.n_dice - keep.len(), rules.n_faces));
+                }
+                let before = sheet.sum(&rules) as f64;
+                let (score_t, value) = solver.best_category(&sheet, &numbers).unwrap();
+                assert_eq!(
+                    solver.category_value(&sheet, &numbers, score_t).unwrap(),
+                    Some(value)
+                );
+                check_score_type(&sheet, score_t, &numbers, &rules).unwrap();
+                update_score_sheet(&mut sheet, score_t, &numbers, &rules);
+                let after = solver.expected_final_score(&sheet);
+                assert!((before + value - after).abs() < 1e-9);
+            }
+        }
+    }
+}
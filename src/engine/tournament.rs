@@ -0,0 +1,186 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use std::sync::{Arc, Mutex};
+
+use super::simulation::solve_for;
+use super::{create_bot, play_game_headless, Dice, Difficulty, Ruleset, Seat};
+
+const INITIAL_RATING: f64 = 1500.0;
+/// Elo K-factor for a two player game, split between all opponents of a match
+const K_FACTOR: f64 = 32.0;
+
+/// One line of the leaderboard.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standing {
+    pub name: String,
+    pub rating: f64,
+    pub wins: usize,
+    pub losses: usize,
+    pub ties: usize,
+    pub mean_score: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TournamentResult {
+    /// in the order of the entrants
+    pub standings: Vec<Standing>,
+    /// `head_to_head[i][j]` is the number of matches entrant i scored more than entrant j
+    pub head_to_head: Vec<Vec<usize>>,
+}
+
+impl TournamentResult {
+    /// The standings sorted by rating, best first.
+    pub fn leaderboard(&self) -> Vec<&Standing> {
+        let mut leaderboard: Vec<&Standing> = self.standings.iter().collect();
+        leaderboard.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+        return leaderboard;
+    }
+}
+
+/// Play `n_matches` games in which all entrants play against each other, with the seats
+/// rotated every match. Every match counts as a win, loss or tie against every opponent,
+/// ranked by the sum of the score card. Elo ratings are updated after each match in order.
+pub fn run_tournament(
+    entrants: &[(String, Difficulty)],
+    rules: &Ruleset,
+    n_matches: usize,
+    seed: u64,
+) -> Result<TournamentResult, String> {
+    if entrants.len() < 2 {
+        return Err(String::from("a tournament needs at least two entrants"));
+    }
+    if n_matches == 0 {
+        return Err(String::from("at least one match is needed"));
+    }
+    let difficulties: Vec<Difficulty> = entrants.iter().map(|(_, level)| *level).collect();
+    let solver = solve_for(&difficulties, rules)?;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let seeds: Vec<u64> = (0..n_matches).map(|_| rng.gen()).collect();
+
+    // the sums of every match in the order of the entrants
+    let matches: Vec<Vec<i32>> = seeds
+        .into_par_iter()
+        .enumerate()
+        .map_init(
+            || solver.clone().map(|solver| Arc::new(Mutex::new(solver))),
+            |solver, (round, match_seed)| {
+                let mut match_rng = StdRng::seed_from_u64(match_seed);
+                let n = entrants.len();
+                let order: Vec<usize> = (0..n).map(|seat| (seat + round) % n).collect();
+                let mut seats: Vec<Seat> = Vec::with_capacity(n);
+                for entrant in &order {
+                    let bot = create_bot(difficulties[*entrant], match_rng.gen(), solver.clone())?;
+                    seats.push((entrants[*entrant].0.clone(), bot));
+                }
+                let dice = Dice::seeded(match_rng.gen());
+                let game = play_game_headless(seats, dice, rules.clone())?;
+                let mut sums = vec![0; n];
+                for (seat, entrant) in order.iter().enumerate() {
+                    sums[*entrant] = game.score_cards()[seat].sum(rules);
+                }
+                return Ok(sums);
+            },
+        )
+        .collect::<Result<_, String>>()?;
+    return Ok(rate(entrants, &matches));
+}
+
+fn rate(entrants: &[(String, Difficulty)], matches: &[Vec<i32>]) -> TournamentResult {
+    let n = entrants.len();
+    let mut standings: Vec<Standing> = entrants
+        .iter()
+        .map(|(name, _)| Standing {
+            name: name.clone(),
+            rating: INITIAL_RATING,
+            wins: 0,
+            losses: 0,
+            ties: 0,
+            mean_score: 0.0,
+        })
+        .collect();
+    let mut head_to_head = vec![vec![0; n]; n];
+    let k = K_FACTOR / (n - 1) as f64;
+    for sums in matches {
+        let ratings: Vec<f64> = standings.iter().map(|standing| standing.rating).collect();
+        for i in 0..n {
+            standings[i].mean_score += sums[i] as f64 / matches.len() as f64;
+            for j in 0..n {
+                if i == j {
+                    continue;
+                }
+                let score = match sums[i].cmp(&sums[j]) {
+                    std::cmp::Ordering::Greater => {
+                        standings[i].wins += 1;
+                        head_to_head[i][j] += 1;
+                        1.0
+                    }
+                    std::cmp::Ordering::Less => {
+                        standings[i].losses += 1;
+                        0.0
+                    }
+                    std::cmp::Ordering::Equal => {
+                        standings[i].ties += 1;
+                        0.5
+                    }
+                };
+                let expected = 1.0 / (1.0 + 10f64.powf((ratings[j] - ratings[i]) / 400.0));
+                standings[i].rating += k * (score - expected);
+            }
+        }
+    }
+    return TournamentResult {
+        standings,
+        head_to_head,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::ScoreType;
+
+    #[test]
+    fn ratings() {
+        let entrants = vec![
+            (String::from("a"), Difficulty::Easy),
+            (String::from("b"), Difficulty::Easy),
+            (String::from("c"), Difficulty::Easy),
+        ];
+        let result = rate(&entrants, &[vec![10, 5, 5], vec![10, 20, 0]]);
+        let a = &result.standings[0];
+        assert_eq!((a.wins, a.losses, a.ties), (3, 1, 0));
+        assert_eq!(a.mean_score, 10.0);
+        assert_eq!(result.standings[1].ties, 1);
+        assert_eq!(result.head_to_head[0], vec![0, 1, 2]);
+        assert_eq!(result.head_to_head[1], vec![1, 0, 1]);
+        let total: f64 = result.standings.iter().map(|s| s.rating).sum();
+        assert!((total - 3.0 * INITIAL_RATING).abs() < 1e-9);
+        assert_eq!(result.leaderboard()[0].name, "a");
+    }
+
+    #[test]
+    fn bots() {
+        let rules = Ruleset {
+            categories: vec![
+                ScoreType::Sixes,
+                ScoreType::FullHouse,
+                ScoreType::Yahtzee,
+                ScoreType::Chance,
+            ],
+            ..Ruleset::kniffel().with_dice(4, 6)
+        };
+        let entrants = vec![
+            (String::from("easy"), Difficulty::Easy),
+            (String::from("optimal"), Difficulty::Optimal { noise: 0.0 }),
+        ];
+        let result = run_tournament(&entrants, &rules, 100, 5).unwrap();
+        assert_eq!(result, run_tournament(&entrants, &rules, 100, 5).unwrap());
+        assert_eq!(result.leaderboard()[0].name, "optimal");
+        assert!(result.head_to_head[1][0] > result.head_to_head[0][1]);
+        let easy = &result.standings[0];
+        assert_eq!(easy.wins + easy.losses + easy.ties, 100);
+        assert!(run_tournament(&entrants[..1], &rules, 10, 5).is_err());
+        assert!(run_tournament(&entrants, &rules, 0, 5).is_err());
+    }
+}
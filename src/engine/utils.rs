@@ -1,13 +1,14 @@
 use std::collections::HashMap;
 
-pub fn vecs_elementwise_equal(vec1: &Vec<i32>, vec2: &Vec<i32>) -> bool {
+#[allow(dead_code)]
+pub fn vecs_elementwise_equal(vec1: &[i32], vec2: &[i32]) -> bool {
     if vec1.len() != vec2.len() {
         return false;
     }
     return vec1.iter().zip(vec2).filter(|&(a, b)| a == b).count() == vec1.len();
 }
 
-pub fn count_unique_elements(vec: &Vec<i32>) -> HashMap<i32, usize> {
+pub fn count_unique_elements(vec: &[i32]) -> HashMap<i32, usize> {
     let mut counts = HashMap::new();
     for &value in vec.iter() {
         counts
@@ -18,16 +19,6 @@ pub fn count_unique_elements(vec: &Vec<i32>) -> HashMap<i32, usize> {
     return counts;
 }
 
-pub fn set_slice_from_vec(dest: &mut Vec<i32>, src: Vec<i32>, start_index: usize) {
-    // Check if the starting index is within bounds
-    if start_index + src.len() > dest.len() {
-        panic!("Source vector is too large for destination vector at the given index.");
-    }
-
-    // Copy the elements from src to the appropriate slice in dest
-    dest[start_index..start_index + src.len()].copy_from_slice(&src);
-}
-
 #[cfg(test)]
 mod tests {
 
@@ -1 +1,5 @@
+#![allow(clippy::needless_return)]
+
+pub mod cli;
 pub mod engine;
+pub mod tui;
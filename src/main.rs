@@ -1,5 +1,212 @@
+#![allow(clippy::needless_return)]
+
+use clap::{Args, Parser, Subcommand};
+use kniffel::cli::{parse_rules, parse_strategy, sim, SimArgs};
 use kniffel::engine;
+use kniffel::engine::probability::Calculator;
+use kniffel::engine::record::{print_replay, GameRecord};
+use kniffel::engine::solver::Solver;
+use kniffel::engine::{Column, PlayOptions, Ruleset, ScoreSheet, ScoreType};
+use kniffel::tui;
+use std::env;
+use std::io::{stdout, IsTerminal};
+use std::path::PathBuf;
+use std::process;
+
+/// Kniffel (Yahtzee) on the console, with computer opponents, simulations and a solver.
+#[derive(Parser)]
+#[command(version, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    play: PlayArgs,
+    /// do not color the output, also disabled by the NO_COLOR environment variable
+    #[arg(long, global = true)]
+    no_color: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Play a game on the console, the default without a subcommand
+    Play(PlayArgs),
+    /// Simulate games of one bot strategy or a tournament between strategies
+    Sim(SimArgs),
+    /// Expected final score and best decisions of the optimal strategy
+    Solve(SolveArgs),
+    /// Step through a recorded game turn by turn and verify it
+    Replay {
+        /// record file, JSON or notation ending in .kn
+        file: PathBuf,
+    },
+    /// Probabilities to reach combinations with the kept dice
+    Prob(ProbArgs),
+}
+
+#[derive(Args)]
+struct PlayArgs {
+    /// human players in seating order, e.g. alice,bob
+    #[arg(long, value_delimiter = ',')]
+    players: Vec<String>,
+    /// computer opponents seated after the humans: easy, greedy, optimal or optimal:<noise>
+    #[arg(long, value_delimiter = ',', value_parser = parse_bot)]
+    bots: Vec<String>,
+    /// dice seed, random if not given
+    #[arg(long)]
+    seed: Option<u64>,
+    /// kniffel, yahtzee, yacht, yatzy, maxi, triple or block, asked for if not given
+    #[arg(long, value_parser = parse_rules)]
+    rules: Option<Ruleset>,
+    /// replace the columns of the rules, e.g. down,up,free,ann or free,free:2,free:3
+    #[arg(long, value_delimiter = ',', value_parser = Column::from_name)]
+    columns: Vec<Column>,
+    /// continue a saved game
+    #[arg(long, conflicts_with_all = ["players", "bots", "seed", "rules", "columns"])]
+    resume: Option<PathBuf>,
+    /// write the record of the game to this file, in the notation if it ends in .kn
+    #[arg(long)]
+    record: Option<PathBuf>,
+    /// play in a full screen terminal interface, one human with kniffel rules by default
+    #[arg(long)]
+    tui: bool,
+}
+
+#[derive(Args)]
+struct SolveArgs {
+    #[arg(long, value_parser = parse_rules, default_value = "kniffel")]
+    rules: Ruleset,
+    /// boxes already written, e.g. 1:3,fh:25
+    #[arg(long, value_delimiter = ',')]
+    scores: Vec<String>,
+    /// throws left after the current one, 0 to pick a box
+    #[arg(long)]
+    throws_left: Option<usize>,
+    /// the numbers of the current throw, without them the expected final score is shown
+    numbers: Vec<i32>,
+}
+
+#[derive(Args)]
+struct ProbArgs {
+    #[arg(long, value_parser = parse_rules, default_value = "kniffel")]
+    rules: Ruleset,
+    /// throws left including the next one, all throws of a turn if not given
+    #[arg(long)]
+    throws: Option<usize>,
+    /// the numbers kept so far
+    kept: Vec<i32>,
+}
+
+fn parse_bot(name: &str) -> Result<String, String> {
+    parse_strategy(name)?;
+    return Ok(format!("bot:{name}"));
+}
+
+fn play(args: PlayArgs) -> Result<(), String> {
+    if let Some(path) = args.resume {
+        return match args.tui {
+            true => tui::resume(&path, args.record),
+            false => engine::resume_game(&path, args.record.as_deref()),
+        };
+    }
+    let mut rules = args.rules;
+    if !args.columns.is_empty() {
+        let mut with_columns = rules.unwrap_or_default();
+        with_columns.columns = args.columns;
+        rules = Some(with_columns);
+    }
+    let players = [args.players, args.bots].concat();
+    let options = PlayOptions {
+        seed: args.seed,
+        rules,
+        players: Some(players).filter(|players| !players.is_empty()),
+        record: args.record,
+    };
+    if args.tui {
+        return tui::run(options);
+    }
+    engine::play_game(options);
+    return Ok(());
+}
+
+fn solve(args: SolveArgs) -> Result<(), String> {
+    let rules = args.rules;
+    let mut scores = Vec::with_capacity(args.scores.len());
+    for score in &args.scores {
+        let (abbreviation, points) = score
+            .split_once(':')
+            .ok_or(format!("{score} is not a box with points like fh:25"))?;
+        let score_t: ScoreType = *rules
+            .categories
+            .iter()
+            .find(|score_t| score_t.abbreviation() == abbreviation)
+            .ok_or(format!("{abbreviation} is not a box of these rules"))?;
+        let points = points
+            .parse()
+            .map_err(|_| format!("invalid points {points}"))?;
+        scores.push((score_t, points));
+    }
+    let throws_left = args.throws_left.unwrap_or(rules.n_throws.saturating_sub(1));
+    if throws_left >= rules.n_throws {
+        return Err(format!(
+            "--throws-left must be less than the {} throws of a turn",
+            rules.n_throws
+        ));
+    }
+    let sheet = ScoreSheet::from_scores(&scores);
+    let mut solver = Solver::new(rules.clone())?;
+    println!("computing the best strategy, this takes a while");
+    let expected = solver.expected_final_score(&sheet);
+    println!("expected final score: {expected:.2}");
+    if args.numbers.is_empty() {
+        return Ok(());
+    }
+    let sum = sheet.sum(&rules) as f64;
+    if throws_left == 0 {
+        for (score_t, value) in solver.ranked_categories(&sheet, &args.numbers)? {
+            println!("{:<25}{:>8.2}", score_t.name(), sum + value);
+        }
+    } else {
+        for (kept, value) in solver.ranked_keeps(&sheet, &args.numbers, throws_left)? {
+            println!("{:<25}{:>8.2}", format!("keep {kept:?}"), sum + value);
+        }
+    }
+    return Ok(());
+}
+
+fn print_probabilities(args: ProbArgs) -> Result<(), String> {
+    let rules = args.rules;
+    let throws_left = args.throws.unwrap_or(rules.n_throws);
+    let kept = args.kept;
+    println!(
+        "keeping {kept:?} with {throws_left} throws left ({} rules)",
+        rules.name
+    );
+    let calculator = Calculator::new(rules);
+    for (combination, probability) in calculator.probabilities(&kept, throws_left)? {
+        println!(
+            "{:<25}{:>8.3}%",
+            combination.to_string(),
+            100.0 * probability
+        );
+    }
+    return Ok(());
+}
 
 fn main() {
-    engine::play_game();
+    let cli = Cli::parse();
+    engine::set_color(!cli.no_color && env::var_os("NO_COLOR").is_none() && stdout().is_terminal());
+    let result = match cli.command {
+        None => play(cli.play),
+        Some(Command::Play(args)) => play(args),
+        Some(Command::Sim(args)) => sim(args),
+        Some(Command::Solve(args)) => solve(args),
+        Some(Command::Replay { file }) => {
+            GameRecord::load(&file).map(|record| print_replay(&record))
+        }
+        Some(Command::Prob(args)) => print_probabilities(args),
+    };
+    if let Err(e) = result {
+        eprintln!("{e}");
+        process::exit(1);
+    }
 }
@@ -0,0 +1,659 @@
+use ratatui::backend::Backend;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Cell, Paragraph, Row, Table};
+use ratatui::{Frame, Terminal};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::engine::record::GameRecord;
+use crate::engine::{
+    ask_to_continue, autosave_path, create_seats, potential_score, score_gain, unfinished_game,
+    Dice, Difficulty, GameState, PlayOptions, Player, ScoreSheet, ScoreType,
+};
+
+/// Time between two actions of a bot, so they can be followed on screen.
+const BOT_DELAY: Duration = Duration::from_millis(700);
+/// File written by the save key.
+const SAVE_FILE: &str = "kniffel-save.json";
+const HELP: &str = "r roll  1-6 hold  arrows select  enter write  a announce  s save  q quit";
+
+/// The full screen interface around a running game. Humans play with the keyboard, bots
+/// play on their own.
+pub struct App {
+    game: GameState,
+    /// `None` for the humans at the keyboard
+    bots: Vec<Option<Box<dyn Player>>>,
+    /// which dice are held for the next throw, by position
+    held: Vec<bool>,
+    /// the selected box as column and index into the categories of the rules
+    column: usize,
+    row: usize,
+    message: String,
+    /// a box that scores nothing, written if it is chosen a second time in a row
+    scratch: Option<(usize, ScoreType)>,
+    autosave: Option<PathBuf>,
+    /// set when a bot failed, it is not asked again so the game can be quit and saved
+    bot_failed: bool,
+    quit: bool,
+}
+
+impl App {
+    pub fn new(game: GameState, bots: Vec<Option<Box<dyn Player>>>) -> App {
+        return App {
+            held: game.held().to_vec(),
+            game,
+            bots,
+            column: 0,
+            row: 0,
+            message: String::from("press r to roll the dice"),
+            scratch: None,
+            autosave: None,
+            bot_failed: false,
+            quit: false,
+        };
+    }
+
+    /// Save the game to `path` after every scored box and remove it once the game is over.
+    pub fn with_autosave(self, path: PathBuf) -> App {
+        return App {
+            autosave: Some(path),
+            ..self
+        };
+    }
+
+    pub fn game(&self) -> &GameState {
+        return &self.game;
+    }
+
+    fn is_human_turn(&self) -> bool {
+        return self.bots[self.game.current_player()].is_none();
+    }
+
+    /// React to a key press of a human player.
+    pub fn handle_key(&mut self, key: KeyCode) {
+        let n_rows = self.game.rules().categories.len();
+        let n_columns = self.game.rules().columns.len();
+        let scratch = self.scratch.take();
+        let result = match key {
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.quit = true;
+                Ok(())
+            }
+            _ if self.game.is_finished() || !self.is_human_turn() => Ok(()),
+            KeyCode::Char('r') => self.roll(),
+            KeyCode::Char(digit @ '1'..='9') => self.toggle_hold(digit as usize - '1' as usize),
+            KeyCode::Up => {
+                self.row = (self.row + n_rows - 1) % n_rows;
+                Ok(())
+            }
+            KeyCode::Down => {
+                self.row = (self.row + 1) % n_rows;
+                Ok(())
+            }
+            KeyCode::Left => {
+                self.column = (self.column + n_columns - 1) % n_columns;
+                Ok(())
+            }
+            KeyCode::Right => {
+                self.column = (self.column + 1) % n_columns;
+                Ok(())
+            }
+            KeyCode::Enter | KeyCode::Char('w') => self.write(scratch),
+            KeyCode::Char('a') => self.announce(),
+            KeyCode::Char('s') => self.game.save(Path::new(SAVE_FILE)).map(|_| {
+                self.message = format!("game saved to {SAVE_FILE}");
+            }),
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            self.message = e;
+        }
+    }
+
+    fn toggle_hold(&mut self, die: usize) -> Result<(), String> {
+        if self.game.numbers().is_empty() {
+            return Err(String::from("roll the dice first"));
+        }
+        let held = self
+            .held
+            .get_mut(die)
+            .ok_or(format!("there is no die {}", die + 1))?;
+        *held = !*held;
+        return Ok(());
+    }
+
+    fn check_announcement(&self) -> Result<(), String> {
+        if self.game.must_announce() {
+            return Err(String::from(
+                "only announced columns are left, announce a box with a",
+            ));
+        }
+        return Ok(());
+    }
+
+    fn roll(&mut self) -> Result<(), String> {
+        self.check_announcement()?;
+        if !self.game.numbers().is_empty() {
+            if self.game.throws_left() == 0 {
+                return Err(String::from("no throws left, write to a box"));
+            }
+            if self.held.iter().all(|held| *held) {
+                return Err(String::from("all dice are held, write to a box"));
+            }
+            self.game.hold(self.held.clone())?;
+        }
+        self.game.roll()?;
+        self.held = self.game.held().to_vec();
+        self.message = format!("{} throws left", self.game.throws_left());
+        return Ok(());
+    }
+
+    fn selected_box(&self) -> (usize, ScoreType) {
+        return (self.column, self.game.rules().categories[self.row]);
+    }
+
+    fn write(&mut self, scratch: Option<(usize, ScoreType)>) -> Result<(), String> {
+        if self.game.numbers().is_empty() {
+            return Err(String::from("roll the dice first"));
+        }
+        self.check_announcement()?;
+        let (column, score_t) = self.selected_box();
+        let sheet = &self.game.current_score_card().columns()[column];
+        let gain = score_gain(sheet, score_t, self.game.numbers(), self.game.rules());
+        if gain == 0
+            && scratch != Some((column, score_t))
+            && self.game.legal_boxes().contains(&(column, score_t))
+        {
+            self.scratch = Some((column, score_t));
+            self.message = format!(
+                "{} adds 0 points, press enter again to scratch it",
+                score_t.name()
+            );
+            return Ok(());
+        }
+        let points = self.game.choose_category(column, score_t)?;
+        self.message = format!(
+            "{} wrote {points} points to {}",
+            self.game.current_player_name(),
+            score_t.name()
+        );
+        return self.finish_turn();
+    }
+
+    fn announce(&mut self) -> Result<(), String> {
+        let (column, score_t) = self.selected_box();
+        self.game.announce(column, score_t)?;
+        self.message = format!("announced {} in column {}", score_t.name(), column + 1);
+        return Ok(());
+    }
+
+    fn finish_turn(&mut self) -> Result<(), String> {
+        self.game.advance_player()?;
+        self.held.fill(false);
+        if let Some(path) = &self.autosave {
+            if self.game.is_finished() {
+                // nothing to recover any more
+                let _ = fs::remove_file(path);
+            } else {
+                self.game.save(path)?;
+            }
+        }
+        return Ok(());
+    }
+
+    /// Let the bot whose turn it is take its next action: a throw, keeping dice followed
+    /// by a throw, or writing a box.
+    pub fn step_bot(&mut self) -> Result<(), String> {
+        let player = self.game.current_player();
+        let bot = self.bots[player]
+            .as_mut()
+            .ok_or(String::from("it is the turn of a human"))?;
+        let game = &mut self.game;
+        let n_dice = game.rules().n_dice;
+        if game.numbers().is_empty() {
+            game.roll()?;
+            if game.can_announce() {
+                if let Some((column, score_t)) = bot.decide_announcement(game) {
+                    game.announce(column, score_t)?;
+                }
+            }
+            self.message = format!("{} threw", game.current_player_name());
+            return Ok(());
+        }
+        if game.throws_left() > 0 && game.kept_numbers().len() < n_dice {
+            game.hold(bot.decide_held_dice(game)?)?;
+            let kept = game.kept_numbers();
+            self.message = format!("{} keeps {kept:?}", game.current_player_name());
+            if kept.len() < n_dice {
+                game.roll()?;
+            }
+            self.held = game.held().to_vec();
+            return Ok(());
+        }
+        let (column, score_t) = bot.decide_scoresheet_update(game)?;
+        let points = game.choose_category(column, score_t)?;
+        self.message = format!(
+            "{} wrote {points} points to {}",
+            game.current_player_name(),
+            score_t.name()
+        );
+        return self.finish_turn();
+    }
+
+    /// Let the bot take its next action like `step_bot`, showing that it is thinking while
+    /// it decides, the first decision of the optimal bot solves the rules. An error of the
+    /// bot is shown and stops the bots, only errors of the terminal are returned.
+    fn step_bot_on_screen<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), String> {
+        self.message = format!("{} is thinking", self.game.current_player_name());
+        terminal
+            .draw(|frame| self.draw(frame))
+            .map_err(|e| e.to_string())?;
+        if let Err(e) = self.step_bot() {
+            self.message = format!(
+                "{} cannot go on: {e}. press q to quit, the game is saved",
+                self.game.current_player_name()
+            );
+            self.bot_failed = true;
+        }
+        return Ok(());
+    }
+
+    fn event_loop<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), String> {
+        while !self.quit {
+            terminal
+                .draw(|frame| self.draw(frame))
+                .map_err(|e| e.to_string())?;
+            let bot_turn = !self.game.is_finished() && !self.is_human_turn() && !self.bot_failed;
+            // bots act after a short delay unless a key is pressed
+            if bot_turn && !event::poll(BOT_DELAY).map_err(|e| e.to_string())? {
+                self.step_bot_on_screen(terminal)?;
+                continue;
+            }
+            if let Event::Key(key) = event::read().map_err(|e| e.to_string())? {
+                if key.kind == KeyEventKind::Press {
+                    self.handle_key(key.code);
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [status, dice, sheet, message, help] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(5),
+            Constraint::Min(0),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        frame.render_widget(Paragraph::new(self.status_line()), status);
+        self.draw_dice(frame, dice);
+        frame.render_widget(self.sheet_table(), sheet);
+        frame.render_widget(Paragraph::new(self.message.as_str()), message);
+        frame.render_widget(
+            Paragraph::new(HELP).style(Style::new().fg(Color::DarkGray)),
+            help,
+        );
+    }
+
+    fn status_line(&self) -> String {
+        let results: Vec<String> = self
+            .game
+            .results()
+            .iter()
+            .map(|(name, sum)| format!("{name} {sum}"))
+            .collect();
+        if self.game.is_finished() {
+            return format!("game over | {}", results.join(", "));
+        }
+        let mut status = format!(
+            "turn {}/{} | {} | throws left {} | {}",
+            self.game.turn(),
+            self.game.rules().n_turns(),
+            self.game.current_player_name(),
+            self.game.throws_left(),
+            results.join(", ")
+        );
+        if let Some((column, score_t)) = self.game.announcement() {
+            status.push_str(&format!(
+                " | announced {} in column {}",
+                score_t.name(),
+                column + 1
+            ));
+        }
+        return status;
+    }
+
+    fn draw_dice(&self, frame: &mut Frame, area: Rect) {
+        let n_dice = self.game.rules().n_dice;
+        let areas = Layout::horizontal(vec![Constraint::Length(9); n_dice]).split(area);
+        for (die, die_area) in areas.iter().enumerate() {
+            let mut block = Block::bordered().title(format!("{}", die + 1));
+            if self.held[die] {
+                block = block
+                    .title_bottom("held")
+                    .border_style(Style::new().fg(Color::Yellow));
+            }
+            let face = match self.game.numbers().get(die) {
+                Some(number) => face_lines(*number),
+                None => vec![String::new(); 3],
+            };
+            frame.render_widget(Paragraph::new(face.join("\n")).block(block), *die_area);
+        }
+    }
+
+    fn sheet_table(&self) -> Table<'_> {
+        let game = &self.game;
+        let rules = game.rules();
+        let several_columns = rules.columns.len() > 1;
+        let mut header = vec![Cell::from("")];
+        for name in game.players() {
+            for column in &rules.columns {
+                match several_columns {
+                    true => header.push(Cell::from(format!("{name} {}", column.label()))),
+                    false => header.push(Cell::from(name.as_str())),
+                }
+            }
+        }
+        let legal = match game.numbers().is_empty() || !self.is_human_turn() {
+            true => vec![],
+            false => game.legal_boxes(),
+        };
+        let mut rows = Vec::new();
+        for (row, score_t) in rules.categories.iter().enumerate() {
+            let mut cells = vec![Cell::from(score_t.name())];
+            for (player, card) in game.score_cards().iter().enumerate() {
+                for (column, sheet) in card.columns().iter().enumerate() {
+                    let is_current = player == game.current_player() && !game.is_finished();
+                    let mut style = Style::new();
+                    let text = match sheet.get_score(*score_t) {
+                        Some(points) => points.to_string(),
+                        None if is_current && legal.contains(&(column, *score_t)) => {
+                            style = style.fg(Color::Green);
+                            let points = potential_score(sheet, *score_t, game.numbers(), rules);
+                            format!("+{points}")
+                        }
+                        None => String::new(),
+                    };
+                    if is_current
+                        && self.is_human_turn()
+                        && (column, row) == (self.column, self.row)
+                    {
+                        style = style.add_modifier(Modifier::REVERSED);
+                    }
+                    cells.push(Cell::from(text).style(style));
+                }
+            }
+            rows.push(Row::new(cells));
+        }
+        let sums = |name: &'static str, points: &dyn Fn(&ScoreSheet) -> i32| {
+            let mut cells = vec![Cell::from(name)];
+            for card in game.score_cards() {
+                for sheet in card.columns() {
+                    cells.push(Cell::from(points(sheet).to_string()));
+                }
+            }
+            return Row::new(cells).style(Style::new().add_modifier(Modifier::BOLD));
+        };
+        if rules.upper_bonus > 0 {
+            rows.push(sums("Bonus", &|sheet| sheet.upper_bonus(rules)));
+        }
+        if rules.yahtzee_bonus > 0 {
+            rows.push(sums("Yahtzee Bonus", &|sheet| sheet.yahtzee_bonus(rules)));
+        }
+        rows.push(sums("Sum", &|sheet| sheet.sum(rules)));
+
+        let n_cells = game.players().len() * rules.columns.len();
+        let mut widths = vec![Constraint::Length(16)];
+        widths.extend(vec![Constraint::Length(14); n_cells]);
+        return Table::new(rows, widths)
+            .header(Row::new(header).style(Style::new().add_modifier(Modifier::BOLD)))
+            .block(Block::bordered().title("score sheet"));
+    }
+}
+
+fn face_lines(number: i32) -> Vec<String> {
+    // pips on a 3 x 3 grid, dice with more faces show the number
+    let pips: &[usize] = match number {
+        1 => &[4],
+        2 => &[0, 8],
+        3 => &[0, 4, 8],
+        4 => &[0, 2, 6, 8],
+        5 => &[0, 2, 4, 6, 8],
+        6 => &[0, 2, 3, 5, 6, 8],
+        _ => return vec![String::new(), format!("{number:^7}"), String::new()],
+    };
+    return (0..3)
+        .map(|line| {
+            let pip = |column: usize| match pips.contains(&(3 * line + column)) {
+                true => "●",
+                false => " ",
+            };
+            return format!(" {} {} {}", pip(0), pip(1), pip(2));
+        })
+        .collect();
+}
+
+fn play(game: GameState, record: Option<PathBuf>) -> Result<(), String> {
+    let seed = game.dice_seed().unwrap_or_else(Dice::random_seed);
+    let seats = create_seats(game.players().to_vec(), game.rules(), seed)?;
+    let bots = seats
+        .into_iter()
+        .map(|(name, player)| Difficulty::from_name(&name).map(|_| player))
+        .collect();
+    let autosave = autosave_path();
+    let mut app = App::new(game, bots).with_autosave(autosave.clone());
+    let mut terminal = ratatui::init();
+    let result = app.event_loop(&mut terminal);
+    ratatui::restore();
+    // an unfinished game is saved with the throws of the current turn
+    let saved = match app.game.is_finished() {
+        true => Ok(()),
+        false => app.game.save(&autosave),
+    };
+    result?;
+    saved?;
+
+    if !app.game.is_finished() {
+        println!(
+            "the game was saved to {}, it is offered again on the next start",
+            autosave.display()
+        );
+        return Ok(());
+    }
+    for (player, sum) in app.game.results() {
+        println!("{player}: {sum}");
+    }
+    if let Some(path) = record {
+        GameRecord::from_game(&app.game).save(&path)?;
+        println!("game record written to {}", path.display());
+    }
+    return Ok(());
+}
+
+/// Play a game in the full screen interface. Without setup options an unfinished game is
+/// offered, otherwise nothing is asked for: without players one human plays, without
+/// rules Kniffel is played.
+pub fn run(options: PlayOptions) -> Result<(), String> {
+    if let Some(game) = unfinished_game(&autosave_path()).filter(|_| !options.sets_up_game()) {
+        if ask_to_continue(&game) {
+            return play(game, options.record);
+        }
+    }
+    let seed = options.seed.unwrap_or_else(Dice::random_seed);
+    let names = options
+        .players
+        .unwrap_or_else(|| vec![String::from("player")]);
+    let game = GameState::new(names, Dice::seeded(seed), options.rules.unwrap_or_default());
+    return play(game, options.record);
+}
+
+/// Continue a saved game in the full screen interface.
+pub fn resume(path: &Path, record: Option<PathBuf>) -> Result<(), String> {
+    return play(GameState::load(path)?, record);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{GreedyBot, Ruleset};
+    use ratatui::backend::TestBackend;
+
+    fn press(app: &mut App, keys: &[KeyCode]) {
+        for key in keys {
+            app.handle_key(*key);
+        }
+    }
+
+    #[test]
+    fn keyboard_turn() {
+        let game = GameState::new(
+            vec![String::from("alice")],
+            Dice::scripted(vec![5, 4, 3, 2, 1, 6, 6, 6]),
+            Ruleset::kniffel(),
+        );
+        let mut app = App::new(game, vec![None]);
+        app.handle_key(KeyCode::Char('1'));
+        assert_eq!(app.message, "roll the dice first");
+        press(
+            &mut app,
+            &[KeyCode::Char('r'), KeyCode::Char('4'), KeyCode::Char('5')],
+        );
+        assert_eq!(app.game().numbers(), [5, 4, 3, 2, 1]);
+        press(
+            &mut app,
+            &[KeyCode::Char('2'), KeyCode::Char('2'), KeyCode::Char('r')],
+        );
+        // the held dice stay in place
+        assert_eq!(app.game().numbers(), [6, 6, 6, 2, 1]);
+        assert_eq!(app.held, [false, false, false, true, true]);
+
+        // a box without points is only written when chosen twice
+        press(&mut app, &[KeyCode::Down, KeyCode::Down, KeyCode::Down]);
+        press(&mut app, &[KeyCode::Enter]);
+        assert_eq!(app.game().turn(), 1);
+        assert_eq!(
+            app.message,
+            "Fours adds 0 points, press enter again to scratch it"
+        );
+        // the chance is the last box
+        press(
+            &mut app,
+            &[KeyCode::Up, KeyCode::Up, KeyCode::Up, KeyCode::Up],
+        );
+        press(&mut app, &[KeyCode::Enter]);
+        let sheet = &app.game().score_cards()[0].columns()[0];
+        assert_eq!(sheet.get_score(ScoreType::Chance), Some(21));
+        assert_eq!(app.game().turn(), 2);
+        assert!(app.game().numbers().is_empty());
+        assert_eq!(app.held, [false; 5]);
+    }
+
+    #[test]
+    fn continue_turn() {
+        let mut game = GameState::new(
+            vec![String::from("alice")],
+            Dice::scripted(vec![1, 2, 3, 4, 5, 6, 6, 6, 6, 6, 6, 6]),
+            Ruleset::kniffel(),
+        );
+        game.roll().unwrap();
+        game.hold(vec![false, true, false, false, true]).unwrap();
+        game.roll().unwrap();
+        // the dice held in a saved turn are still held
+        let mut app = App::new(game, vec![None]);
+        assert_eq!(app.held, [false, true, false, false, true]);
+        press(&mut app, &[KeyCode::Char('5'), KeyCode::Char('r')]);
+        assert_eq!(app.game().numbers(), [6, 2, 6, 6, 6]);
+    }
+
+    #[test]
+    fn bot_turns() {
+        let game = GameState::new(
+            vec![String::from("alice"), String::from("bot:greedy")],
+            Dice::seeded(5),
+            Ruleset::kniffel(),
+        );
+        let mut app = App::new(game, vec![None, Some(Box::new(GreedyBot))]);
+        press(&mut app, &[KeyCode::Char('r'), KeyCode::Enter]);
+        assert_eq!(app.game().current_player(), 1);
+        // keys of humans are ignored during the turn of a bot
+        app.handle_key(KeyCode::Char('r'));
+        assert!(app.game().numbers().is_empty());
+        while app.game().current_player() == 1 {
+            app.step_bot().unwrap();
+        }
+        assert_eq!(app.game().history().last().unwrap().player, 1);
+        assert!(app.step_bot().is_err());
+    }
+
+    #[test]
+    fn failing_bot() {
+        struct FailingBot;
+        impl Player for FailingBot {
+            fn decide_keep_dice(&mut self, game: &GameState) -> Vec<i32> {
+                return game.numbers().to_vec();
+            }
+            fn decide_scoresheet_update(
+                &mut self,
+                _game: &GameState,
+            ) -> Result<(usize, ScoreType), String> {
+                return Err(String::from("no idea"));
+            }
+        }
+
+        let game = GameState::new(
+            vec![String::from("bot:broken")],
+            Dice::seeded(6),
+            Ruleset::kniffel(),
+        );
+        let mut app = App::new(game, vec![Some(Box::new(FailingBot))]);
+        let mut terminal = Terminal::new(TestBackend::new(80, 30)).unwrap();
+        while !app.bot_failed {
+            app.step_bot_on_screen(&mut terminal).unwrap();
+        }
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("bot:broken is thinking"));
+        assert_eq!(
+            app.message,
+            "bot:broken cannot go on: no idea. press q to quit, the game is saved"
+        );
+        assert!(!app.game().is_finished());
+        assert_eq!(app.game().numbers().len(), 5);
+    }
+
+    #[test]
+    fn screen() {
+        let game = GameState::new(
+            vec![String::from("alice"), String::from("bob")],
+            Dice::scripted(vec![6, 6, 6, 2, 2]),
+            Ruleset::kniffel(),
+        );
+        let mut app = App::new(game, vec![None, None]);
+        press(&mut app, &[KeyCode::Char('r'), KeyCode::Char('4')]);
+        let mut terminal = Terminal::new(TestBackend::new(80, 30)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("turn 1/13 | alice | throws left 2"));
+        assert!(screen.contains("held"));
+        assert!(screen.contains("●"));
+        assert!(screen.contains("Full House"));
+        assert!(screen.contains("+25"));
+        assert!(screen.contains("+18"));
+    }
+}